@@ -0,0 +1,177 @@
+//! CPU affinity of threads on Linux.
+//!
+//! The threads of a [`Pool`](crate::Pool) are pinned using
+//! [`PoolBuilder::pin_threads`](crate::pool::PoolBuilder::pin_threads). With the tokio
+//! backends, the threads of the global executor are pinned using `pin_global_threads`:
+//!
+//! ```ignore
+//! agnostik::affinity::pin_global_threads(vec![2, 3], false)?;
+//! ```
+//!
+//! The other backends start the threads of the global executor in their own crates, so
+//! workloads that need pinned threads run on a pool there.
+
+#[cfg(any(tokio, tokio1))]
+use once_cell::sync::Lazy;
+#[cfg(any(tokio, tokio1))]
+use std::sync::{Arc, RwLock};
+use std::{io, mem};
+
+/// The number of CPUs that fit into a mask, like `CPU_SETSIZE` of glibc.
+const CPU_SETSIZE: usize = 1024;
+const BITS: usize = mem::size_of::<libc::c_ulong>() * 8;
+
+type CpuSet = [libc::c_ulong; CPU_SETSIZE / BITS];
+
+/// The CPUs that blocking tasks of the global executor keep, while its threads are pinned.
+#[cfg(any(tokio, tokio1))]
+static GLOBAL_BLOCKING_CPUS: Lazy<RwLock<Option<Arc<[usize]>>>> = Lazy::new(Default::default);
+
+/// Pins the current thread to the CPUs with the given ids, using `sched_setaffinity`.
+///
+/// # Errors
+///
+/// Returns an error if the set is empty, contains an id that is too large, or contains
+/// no CPU that the thread is allowed to run on.
+pub fn pin_current_thread(cpus: &[usize]) -> io::Result<()> {
+    if cpus.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no CPU ids were given",
+        ));
+    }
+    let mut set: CpuSet = [0; CPU_SETSIZE / BITS];
+    for &cpu in cpus {
+        if cpu >= CPU_SETSIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("CPU id {} is too large", cpu),
+            ));
+        }
+        set[cpu / BITS] |= 1 << (cpu % BITS);
+    }
+
+    // SAFETY: the mask is valid for reads of its size
+    let res = unsafe { libc::sched_setaffinity(0, mem::size_of::<CpuSet>(), set.as_ptr().cast()) };
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Returns the ids of the CPUs that the current thread may run on, using `sched_getaffinity`.
+///
+/// # Errors
+///
+/// Returns an error if the affinity couldn't be read.
+pub fn current_thread_affinity() -> io::Result<Vec<usize>> {
+    let mut set: CpuSet = [0; CPU_SETSIZE / BITS];
+    // SAFETY: the mask is valid for writes of its size
+    let res =
+        unsafe { libc::sched_getaffinity(0, mem::size_of::<CpuSet>(), set.as_mut_ptr().cast()) };
+    if res != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((0..CPU_SETSIZE)
+        .filter(|cpu| set[cpu / BITS] & (1 << (cpu % BITS)) != 0)
+        .collect())
+}
+
+/// Runs the closure while the current thread is pinned to the CPUs, and restores the
+/// previous affinity afterwards.
+#[cfg(enable)]
+pub(crate) fn pinned<T>(cpus: &[usize], f: impl FnOnce() -> T) -> T {
+    /// Restores the affinity, even if the closure panics.
+    struct Restore(Option<Vec<usize>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(cpus) = &self.0 {
+                let _ = pin_current_thread(cpus);
+            }
+        }
+    }
+
+    let _restore = Restore(current_thread_affinity().ok());
+    let _ = pin_current_thread(cpus);
+    f()
+}
+
+/// Checks that the current thread may run on the CPUs, before threads are pinned to them.
+#[cfg(enable)]
+pub(crate) fn check(cpus: &[usize]) -> io::Result<()> {
+    if cpus.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no CPU ids were given",
+        ));
+    }
+    let allowed = current_thread_affinity()?;
+    match cpus.iter().find(|cpu| !allowed.contains(cpu)) {
+        Some(cpu) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("CPU {} isn't available", cpu),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Replaces the runtime of the global executor with a new one, whose threads are pinned
+/// to the CPUs with the given ids.
+///
+/// Blocking tasks are only pinned if `pin_blocking` is `true`, and otherwise keep the CPUs
+/// of the current thread, like the ones of a pool without
+/// [`pin_blocking_tasks`](crate::pool::PoolBuilder::pin_blocking_tasks). The runtime is
+/// replaced like using `set_runtime`, so tasks that were spawned onto the previous one
+/// are dropped.
+///
+/// # Errors
+///
+/// Returns an error if the CPUs don't exist or aren't available to the current thread,
+/// or if the runtime couldn't be created.
+#[cfg(any(tokio, tokio1))]
+pub fn pin_global_threads(
+    cpus: impl IntoIterator<Item = usize>,
+    pin_blocking: bool,
+) -> io::Result<()> {
+    let cpus: Vec<usize> = cpus.into_iter().collect();
+    check(&cpus)?;
+    let blocking_cpus = if pin_blocking {
+        None
+    } else {
+        Some(current_thread_affinity()?.into())
+    };
+    // tokio runs `on_thread_start` on its blocking threads as well
+    let on_start = move || {
+        // the CPUs were checked above
+        let _ = pin_current_thread(&cpus);
+    };
+
+    #[cfg(tokio)]
+    let runtime = tokio_crate::runtime::Builder::new_multi_thread()
+        .on_thread_start(on_start)
+        .enable_all()
+        .build()?;
+    #[cfg(tokio1)]
+    let runtime = tokio1_crate::runtime::Builder::new_multi_thread()
+        .on_thread_start(on_start)
+        .enable_all()
+        .build()?;
+    *GLOBAL_BLOCKING_CPUS.write().unwrap() = blocking_cpus;
+    crate::set_runtime(runtime);
+    Ok(())
+}
+
+/// Wraps the closure of a blocking task of the global executor, so it keeps the CPUs
+/// that were given to [`pin_global_threads`] while it runs.
+#[cfg(any(tokio, tokio1))]
+pub(crate) fn global_blocking<T>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> impl FnOnce() -> T + Send + 'static {
+    let cpus = GLOBAL_BLOCKING_CPUS.read().unwrap().clone();
+    move || match cpus {
+        Some(cpus) => pinned(&cpus, f),
+        None => f(),
+    }
+}
@@ -0,0 +1,367 @@
+//! A multi-producer, multi-consumer channel where every receiver sees every value.
+//!
+//! The channel retains the last `capacity` values. Sending never waits: if a
+//! receiver falls behind by more than `capacity` values, the oldest values are
+//! overwritten, and the next receive on that receiver returns
+//! [`RecvError::Lagged`] with the number of values it missed. Afterwards the
+//! receiver continues with the oldest value that is still retained.
+//!
+//! New receivers are created by [`Sender::subscribe`], and only see values
+//! which are sent after they were created.
+
+use super::{block_on, wake_all, waiters::WaitQueue, PollFn};
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll},
+};
+
+/// Creates a broadcast channel, which retains at most `capacity` values.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(
+        capacity > 0,
+        "broadcast channel capacity must be greater than zero"
+    );
+
+    let shared = Arc::new(Shared {
+        state: StdMutex::new(State {
+            buffer: VecDeque::with_capacity(capacity),
+            head: 0,
+            senders: 1,
+            receivers: 1,
+            waiters: WaitQueue::new(),
+        }),
+        capacity,
+    });
+    let sender = Sender {
+        shared: shared.clone(),
+    };
+    let receiver = Receiver {
+        shared,
+        next: 0,
+        waiter: None,
+    };
+    (sender, receiver)
+}
+
+struct Shared<T> {
+    state: StdMutex<State<T>>,
+    capacity: usize,
+}
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    /// The position of the first value in the buffer.
+    head: u64,
+    senders: usize,
+    receivers: usize,
+    waiters: WaitQueue,
+}
+
+impl<T> State<T> {
+    /// The position of the next value that will be sent.
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+}
+
+impl<T: Clone> State<T> {
+    /// Receives the value at the position `next`, if it's still retained.
+    fn recv(&self, next: &mut u64) -> Option<Result<T, RecvError>> {
+        if *next < self.head {
+            let missed = self.head - *next;
+            *next = self.head;
+            return Some(Err(RecvError::Lagged(missed)));
+        }
+
+        if *next < self.tail() {
+            #[allow(clippy::cast_possible_truncation)]
+            let idx = (*next - self.head) as usize;
+            *next += 1;
+            return Some(Ok(self.buffer[idx].clone()));
+        }
+
+        if self.senders == 0 {
+            Some(Err(RecvError::Closed))
+        } else {
+            None
+        }
+    }
+}
+
+/// The sending half of a broadcast channel.
+///
+/// The sender can be cloned to send values from multiple tasks.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends a value to all current receivers, and returns the number of receivers.
+    ///
+    /// If the channel is full, the oldest value is overwritten.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if there are no receivers.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let (receivers, wakers) = {
+            let mut state = self.shared.state.lock().unwrap();
+            if state.receivers == 0 {
+                return Err(SendError(value));
+            }
+
+            if state.buffer.len() == self.shared.capacity {
+                state.buffer.pop_front();
+                state.head += 1;
+            }
+            state.buffer.push_back(value);
+            (state.receivers, state.waiters.notify_all())
+        };
+
+        wake_all(wakers);
+        Ok(receivers)
+    }
+
+    /// Creates a new receiver, which receives all values sent after this call.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers += 1;
+        Receiver {
+            shared: self.shared.clone(),
+            next: state.tail(),
+            waiter: None,
+        }
+    }
+
+    /// Returns the number of receivers.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().unwrap().receivers
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.senders -= 1;
+            if state.senders == 0 {
+                state.waiters.notify_all()
+            } else {
+                Vec::new()
+            }
+        };
+        wake_all(wakers);
+    }
+}
+
+/// The sink is always ready, since sending never waits.
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.send(item).map(drop)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("capacity", &self.shared.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a broadcast channel.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    /// The position of the next value this receiver will receive.
+    next: u64,
+    waiter: Option<u64>,
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receives the next value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RecvError::Lagged`] if the receiver missed values, or
+    /// [`RecvError::Closed`] if all senders were dropped and there are no values left.
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        PollFn(|cx: &mut Context<'_>| self.poll_recv(cx)).await
+    }
+
+    /// Tries to receive the next value, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no value available, or if the receiver missed values.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let state = self.shared.state.lock().unwrap();
+        match state.recv(&mut self.next) {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(RecvError::Lagged(missed))) => Err(TryRecvError::Lagged(missed)),
+            Some(Err(RecvError::Closed)) => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receives the next value, blocking the current thread until a value is available.
+    ///
+    /// This must not be called from within an asynchronous task.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`recv`](Receiver::recv).
+    pub fn recv_blocking(&mut self) -> Result<T, RecvError> {
+        block_on(self.recv())
+    }
+
+    /// Polls to receive the next value.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, RecvError>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(result) = state.recv(&mut self.next) {
+            state.waiters.remove(&mut self.waiter);
+            return Poll::Ready(result);
+        }
+
+        state.waiters.register(&mut self.waiter, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Creates a new receiver, which receives all values sent after this call.
+    pub fn resubscribe(&self) -> Self {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers += 1;
+        Self {
+            shared: self.shared.clone(),
+            next: state.tail(),
+            waiter: None,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers -= 1;
+        state.waiters.remove(&mut self.waiter);
+    }
+}
+
+/// The stream yields [`RecvError::Lagged`] if the receiver missed values,
+/// and ends when all senders were dropped.
+impl<T: Clone> Stream for Receiver<T> {
+    type Item = Result<T, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut().poll_recv(cx) {
+            Poll::Ready(Err(RecvError::Closed)) => Poll::Ready(None),
+            Poll::Ready(result) => Poll::Ready(Some(result)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("capacity", &self.shared.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`Sender::send`] if there are no receivers.
+///
+/// It contains the value that couldn't be sent.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("SendError { .. }")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel closed")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`Receiver::recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// All senders were dropped and there are no values left.
+    Closed,
+    /// The receiver fell behind, and missed the given number of values.
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => f.write_str("channel closed"),
+            Self::Lagged(missed) => write!(f, "receiver lagged by {} values", missed),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// There is currently no value available.
+    Empty,
+    /// All senders were dropped and there are no values left.
+    Closed,
+    /// The receiver fell behind, and missed the given number of values.
+    Lagged(u64),
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("channel empty"),
+            Self::Closed => f.write_str("channel closed"),
+            Self::Lagged(missed) => write!(f, "receiver lagged by {} values", missed),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
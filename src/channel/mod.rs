@@ -0,0 +1,93 @@
+//! Runtime independent channels for communication between tasks.
+//!
+//! - [`mpsc`]: bounded and unbounded channels with many senders and a single receiver
+//! - [`oneshot`]: a channel for sending a single value
+//! - [`broadcast`]: a bounded channel where every receiver sees every value
+//! - [`watch`]: a channel that only retains the most recent value
+//!
+//! Receivers implement [`Stream`](futures_core::Stream), and the senders of the
+//! [`mpsc`] and [`broadcast`] channels implement [`Sink`](futures_sink::Sink).
+//!
+//! Every channel also provides blocking variants of its waiting operations
+//! (e.g. [`send_blocking`](mpsc::Sender::send_blocking) and
+//! [`recv_blocking`](mpsc::Receiver::recv_blocking)), which can be used from
+//! synchronous code, like a closure passed to [`spawn_blocking`](crate::spawn_blocking).
+//! They must never be called from within an asynchronous task.
+//!
+//! ```ignore
+//! use agnostik::channel::mpsc;
+//!
+//! agnostik::block_on(async {
+//!     let (tx, mut rx) = mpsc::channel(16);
+//!
+//!     agnostik::spawn_blocking(move || {
+//!         for i in 0..10 {
+//!             tx.send_blocking(i).unwrap();
+//!         }
+//!     });
+//!
+//!     while let Some(i) = rx.recv().await {
+//!         println!("received {}", i);
+//!     }
+//! });
+//! ```
+
+pub mod broadcast;
+pub mod mpsc;
+pub mod oneshot;
+pub mod watch;
+
+pub(crate) mod waiters;
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+/// Wakes all wakers of the iterator.
+fn wake_all(wakers: impl IntoIterator<Item = Waker>) {
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+/// A future that is implemented by a closure.
+pub(crate) struct PollFn<F>(pub(crate) F);
+
+impl<T, F> Future for PollFn<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<T> + Unpin,
+{
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        (self.0)(cx)
+    }
+}
+
+/// Runs the future on the current thread, parking the thread while it's pending.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
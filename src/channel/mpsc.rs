@@ -0,0 +1,652 @@
+//! A multi-producer, single-consumer channel.
+//!
+//! A bounded channel is created by [`channel`], and can hold at most `capacity`
+//! values. If the channel is full, [`Sender::send`] waits until the receiver made
+//! room for the value. An unbounded channel is created by [`unbounded_channel`],
+//! and sending values to it never waits.
+//!
+//! The channel is closed when all senders are dropped, or when the receiver is
+//! dropped or [closed](Receiver::close). After all senders are gone, the receiver
+//! still receives the values which are left in the channel.
+
+use super::{block_on, wake_all, waiters::WaitQueue, PollFn};
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll, Waker},
+};
+
+/// Creates a bounded channel, which can hold at most `capacity` values.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "mpsc channel capacity must be greater than zero");
+
+    let chan = Arc::new(Chan::new(capacity));
+    let sender = Sender {
+        chan: chan.clone(),
+        sink_waiter: None,
+        sink_reserved: false,
+    };
+    (sender, Receiver { chan })
+}
+
+/// Creates an unbounded channel.
+pub fn unbounded_channel<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let chan = Arc::new(Chan::new(usize::MAX));
+    let sender = UnboundedSender { chan: chan.clone() };
+    (sender, UnboundedReceiver(Receiver { chan }))
+}
+
+struct Chan<T> {
+    state: StdMutex<State<T>>,
+    capacity: usize,
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    /// The number of slots which are reserved by senders that are about to send a value.
+    reserved: usize,
+    senders: usize,
+    closed: bool,
+    rx_waker: Option<Waker>,
+    tx_waiters: WaitQueue,
+}
+
+impl<T> Chan<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            state: StdMutex::new(State {
+                queue: VecDeque::new(),
+                reserved: 0,
+                senders: 1,
+                closed: false,
+                rx_waker: None,
+                tx_waiters: WaitQueue::new(),
+            }),
+            capacity,
+        }
+    }
+
+    /// Reserves a slot for a value, or registers the waiter to be woken up
+    /// if a slot is freed.
+    fn poll_reserve(&self, cx: &mut Context<'_>, waiter: &mut Option<u64>) -> Poll<Result<(), ()>> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            state.tx_waiters.remove(waiter);
+            return Poll::Ready(Err(()));
+        }
+
+        // senders that didn't wait yet have to queue up behind the waiting ones
+        let has_room = state.queue.len() + state.reserved < self.capacity;
+        if has_room && (waiter.is_some() || state.tx_waiters.len() == 0) {
+            state.tx_waiters.remove(waiter);
+            state.reserved += 1;
+            return Poll::Ready(Ok(()));
+        }
+
+        state.tx_waiters.register(waiter, cx.waker());
+        Poll::Pending
+    }
+
+    /// Sends a value into the previously reserved slot.
+    fn send_reserved(&self, value: T) -> Result<(), T> {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.reserved -= 1;
+            if state.closed {
+                return Err(value);
+            }
+            state.queue.push_back(value);
+            state.rx_waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    fn release_reserved(&self) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.reserved -= 1;
+            state.tx_waiters.notify_one()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn cancel_waiter(&self, waiter: &mut Option<u64>) {
+        if waiter.is_none() {
+            return;
+        }
+
+        let waker = self.state.lock().unwrap().tx_waiters.cancel(waiter);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            if state.closed {
+                return Err(TrySendError::Closed(value));
+            }
+            if state.tx_waiters.len() > 0 || state.queue.len() + state.reserved >= self.capacity {
+                return Err(TrySendError::Full(value));
+            }
+            state.queue.push_back(value);
+            state.rx_waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(value) = state.queue.pop_front() {
+            let waker = state.tx_waiters.notify_one();
+            drop(state);
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+            return Poll::Ready(Some(value));
+        }
+
+        if state.closed || state.senders == 0 {
+            return Poll::Ready(None);
+        }
+
+        match &state.rx_waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => state.rx_waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(value) = state.queue.pop_front() {
+            let waker = state.tx_waiters.notify_one();
+            drop(state);
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+            Ok(value)
+        } else if state.closed || state.senders == 0 {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    fn close(&self) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            state.tx_waiters.notify_all()
+        };
+        wake_all(wakers);
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    fn add_sender(&self) {
+        self.state.lock().unwrap().senders += 1;
+    }
+
+    fn drop_sender(&self) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.senders -= 1;
+            if state.senders == 0 {
+                state.rx_waker.take()
+            } else {
+                None
+            }
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn drop_receiver(&self) {
+        self.close();
+
+        // drop the remaining values outside of the lock
+        let queue = std::mem::take(&mut self.state.lock().unwrap().queue);
+        drop(queue);
+    }
+}
+
+/// Future that reserves a slot in a bounded channel.
+struct Reserve<'a, T> {
+    chan: &'a Chan<T>,
+    waiter: Option<u64>,
+}
+
+impl<T> Future for Reserve<'_, T> {
+    type Output = Result<(), ()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.chan.poll_reserve(cx, &mut this.waiter)
+    }
+}
+
+impl<T> Drop for Reserve<'_, T> {
+    fn drop(&mut self) {
+        self.chan.cancel_waiter(&mut self.waiter);
+    }
+}
+
+/// The sending half of a bounded channel, created by [`channel`].
+///
+/// The sender can be cloned to send values from multiple tasks.
+pub struct Sender<T> {
+    chan: Arc<Chan<T>>,
+    sink_waiter: Option<u64>,
+    sink_reserved: bool,
+}
+
+impl<T> Sender<T> {
+    /// Sends a value, waiting until there is room in the channel.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver was dropped or closed.
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let reserve = Reserve {
+            chan: &self.chan,
+            waiter: None,
+        };
+        if reserve.await.is_err() {
+            return Err(SendError(value));
+        }
+        self.chan.send_reserved(value).map_err(SendError)
+    }
+
+    /// Tries to send a value, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the channel is full, or the receiver was dropped or closed.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.chan.try_send(value)
+    }
+
+    /// Sends a value, blocking the current thread until there is room in the channel.
+    ///
+    /// This must not be called from within an asynchronous task.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver was dropped or closed.
+    pub fn send_blocking(&self, value: T) -> Result<(), SendError<T>> {
+        block_on(self.send(value))
+    }
+
+    /// Returns `true` if the receiver was dropped or closed.
+    pub fn is_closed(&self) -> bool {
+        self.chan.is_closed()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.chan.add_sender();
+        Self {
+            chan: self.chan.clone(),
+            sink_waiter: None,
+            sink_reserved: false,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.sink_reserved {
+            self.chan.release_reserved();
+        }
+        self.chan.cancel_waiter(&mut self.sink_waiter);
+        self.chan.drop_sender();
+    }
+}
+
+/// The sink waits in [`poll_ready`](Sink::poll_ready) until there is room in the channel.
+///
+/// Since a slot is reserved before the value is passed to the sink, the error
+/// doesn't contain the value.
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<()>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if this.sink_reserved {
+            return Poll::Ready(Ok(()));
+        }
+
+        match this.chan.poll_reserve(cx, &mut this.sink_waiter) {
+            Poll::Ready(Ok(())) => {
+                this.sink_reserved = true;
+                Poll::Ready(Ok(()))
+            }
+            Poll::Ready(Err(())) => Poll::Ready(Err(SendError(()))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        assert!(
+            this.sink_reserved,
+            "`poll_ready` must be called before `start_send`"
+        );
+
+        this.sink_reserved = false;
+        this.chan.send_reserved(item).map_err(|_| SendError(()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if this.sink_reserved {
+            this.sink_reserved = false;
+            this.chan.release_reserved();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("capacity", &self.chan.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a bounded channel, created by [`channel`].
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receives the next value.
+    ///
+    /// Returns `None` if the channel is closed and there are no values left.
+    pub async fn recv(&mut self) -> Option<T> {
+        PollFn(|cx: &mut Context<'_>| self.chan.poll_recv(cx)).await
+    }
+
+    /// Tries to receive the next value, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel is empty, or if it's closed and there are no values left.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.chan.try_recv()
+    }
+
+    /// Receives the next value, blocking the current thread until a value is available.
+    ///
+    /// This must not be called from within an asynchronous task.
+    pub fn recv_blocking(&mut self) -> Option<T> {
+        block_on(self.recv())
+    }
+
+    /// Polls to receive the next value.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.chan.poll_recv(cx)
+    }
+
+    /// Closes the channel, without dropping the receiver.
+    ///
+    /// All further sends will fail, but the values which are left in the
+    /// channel can still be received.
+    pub fn close(&mut self) {
+        self.chan.close();
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.chan.drop_receiver();
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.chan.poll_recv(cx)
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("capacity", &self.chan.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The sending half of an unbounded channel, created by [`unbounded_channel`].
+///
+/// The sender can be cloned to send values from multiple tasks.
+pub struct UnboundedSender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> UnboundedSender<T> {
+    /// Sends a value, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver was dropped or closed.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.chan.try_send(value).map_err(|err| SendError(err.into_inner()))
+    }
+
+    /// Returns `true` if the receiver was dropped or closed.
+    pub fn is_closed(&self) -> bool {
+        self.chan.is_closed()
+    }
+}
+
+impl<T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        self.chan.add_sender();
+        Self {
+            chan: self.chan.clone(),
+        }
+    }
+}
+
+impl<T> Drop for UnboundedSender<T> {
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+
+impl<T> Sink<T> for UnboundedSender<T> {
+    type Error = SendError<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> fmt::Debug for UnboundedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedSender").finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of an unbounded channel, created by [`unbounded_channel`].
+pub struct UnboundedReceiver<T>(Receiver<T>);
+
+impl<T> UnboundedReceiver<T> {
+    /// Receives the next value.
+    ///
+    /// Returns `None` if the channel is closed and there are no values left.
+    pub async fn recv(&mut self) -> Option<T> {
+        self.0.recv().await
+    }
+
+    /// Tries to receive the next value, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel is empty, or if it's closed and there are no values left.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.0.try_recv()
+    }
+
+    /// Receives the next value, blocking the current thread until a value is available.
+    ///
+    /// This must not be called from within an asynchronous task.
+    pub fn recv_blocking(&mut self) -> Option<T> {
+        self.0.recv_blocking()
+    }
+
+    /// Polls to receive the next value.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.0.poll_recv(cx)
+    }
+
+    /// Closes the channel, without dropping the receiver.
+    ///
+    /// All further sends will fail, but the values which are left in the
+    /// channel can still be received.
+    pub fn close(&mut self) {
+        self.0.close();
+    }
+}
+
+impl<T> Stream for UnboundedReceiver<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+impl<T> fmt::Debug for UnboundedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedReceiver").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by the senders if the receiver was dropped or closed.
+///
+/// It contains the value that couldn't be sent.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("SendError { .. }")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel closed")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`Sender::try_send`].
+#[derive(Clone, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full.
+    Full(T),
+    /// The receiver was dropped or closed.
+    Closed(T),
+}
+
+impl<T> TrySendError<T> {
+    /// Returns the value that couldn't be sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Full(value) | Self::Closed(value) => value,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.pad("Full(..)"),
+            Self::Closed(_) => f.pad("Closed(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.write_str("no available capacity"),
+            Self::Closed(_) => f.write_str("channel closed"),
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The channel is currently empty.
+    Empty,
+    /// The channel is closed and there are no values left.
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("channel empty"),
+            Self::Disconnected => f.write_str("channel closed"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
@@ -0,0 +1,249 @@
+//! A channel for sending a single value between tasks.
+//!
+//! The [`Receiver`] is a future that resolves to the sent value, or to an error
+//! if the [`Sender`] was dropped without sending a value.
+
+use super::{block_on, PollFn};
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll, Waker},
+};
+
+/// Creates a new oneshot channel.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(StdMutex::new(State {
+        value: None,
+        complete: false,
+        rx_dropped: false,
+        rx_waker: None,
+        tx_waker: None,
+    }));
+    (
+        Sender {
+            inner: Some(inner.clone()),
+        },
+        Receiver { inner },
+    )
+}
+
+struct State<T> {
+    value: Option<T>,
+    /// Set when the sender sent a value or was dropped.
+    complete: bool,
+    rx_dropped: bool,
+    rx_waker: Option<Waker>,
+    tx_waker: Option<Waker>,
+}
+
+/// The sending half of a oneshot channel.
+pub struct Sender<T> {
+    inner: Option<Arc<StdMutex<State<T>>>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends the value to the receiver.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver was dropped or closed.
+    pub fn send(mut self, value: T) -> Result<(), T> {
+        let inner = self.inner.take().expect("sender is already used");
+
+        let waker = {
+            let mut state = inner.lock().unwrap();
+            state.complete = true;
+            if state.rx_dropped {
+                return Err(value);
+            }
+            state.value = Some(value);
+            state.rx_waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the receiver was dropped or closed.
+    pub fn is_closed(&self) -> bool {
+        match &self.inner {
+            Some(inner) => inner.lock().unwrap().rx_dropped,
+            None => true,
+        }
+    }
+
+    /// Waits until the receiver was dropped or closed.
+    pub async fn closed(&mut self) {
+        PollFn(|cx: &mut Context<'_>| self.poll_closed(cx)).await;
+    }
+
+    /// Polls whether the receiver was dropped or closed.
+    pub fn poll_closed(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let Some(inner) = &self.inner else {
+            return Poll::Ready(());
+        };
+
+        let mut state = inner.lock().unwrap();
+        if state.rx_dropped {
+            return Poll::Ready(());
+        }
+
+        match &state.tx_waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => state.tx_waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let Some(inner) = self.inner.take() else { return };
+
+        let waker = {
+            let mut state = inner.lock().unwrap();
+            state.complete = true;
+            state.rx_waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+/// The receiving half of a oneshot channel.
+///
+/// The receiver is a future that resolves to the value, once it was sent.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Receiver<T> {
+    inner: Arc<StdMutex<State<T>>>,
+}
+
+impl<T> Receiver<T> {
+    /// Tries to receive the value, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value wasn't sent yet, or if the sender was dropped.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut state = self.inner.lock().unwrap();
+        if let Some(value) = state.value.take() {
+            Ok(value)
+        } else if state.complete {
+            Err(TryRecvError::Closed)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Receives the value, blocking the current thread until it was sent.
+    ///
+    /// This must not be called from within an asynchronous task.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender was dropped without sending a value.
+    pub fn recv_blocking(self) -> Result<T, RecvError> {
+        block_on(self)
+    }
+
+    /// Closes the channel, without dropping the receiver.
+    ///
+    /// A value that was sent before can still be received.
+    pub fn close(&mut self) {
+        let waker = {
+            let mut state = self.inner.lock().unwrap();
+            state.rx_dropped = true;
+            state.tx_waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.inner.lock().unwrap();
+        if let Some(value) = state.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if state.complete {
+            return Poll::Ready(Err(RecvError(())));
+        }
+
+        match &state.rx_waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => state.rx_waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.close();
+
+        // drop a value that was never received outside of the lock
+        let value = self.inner.lock().unwrap().value.take();
+        drop(value);
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.inner.lock().unwrap();
+        f.debug_struct("Receiver")
+            .field("complete", &state.complete)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by the [`Receiver`] if the sender was dropped without sending a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel closed")
+    }
+}
+
+impl Error for RecvError {}
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The value wasn't sent yet.
+    Empty,
+    /// The sender was dropped without sending a value, or the value was already received.
+    Closed,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("channel empty"),
+            Self::Closed => f.write_str("channel closed"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
@@ -0,0 +1,73 @@
+use std::{collections::VecDeque, task::Waker};
+
+/// A FIFO queue of tasks that wait for the state of a channel to change.
+///
+/// Every waiting future keeps the id of its entry, so it can update its waker
+/// and remove the entry when it's dropped.
+pub(crate) struct WaitQueue {
+    next_id: u64,
+    queue: VecDeque<(u64, Waker)>,
+}
+
+impl WaitQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            next_id: 0,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Registers the waker for the given entry, adding it to the back of the queue
+    /// if it isn't already queued.
+    pub(crate) fn register(&mut self, id: &mut Option<u64>, waker: &Waker) {
+        if let Some(id) = *id {
+            if let Some((_, queued)) = self.queue.iter_mut().find(|(queued, _)| *queued == id) {
+                if !queued.will_wake(waker) {
+                    queued.clone_from(waker);
+                }
+                return;
+            }
+        }
+
+        let new_id = self.next_id;
+        self.next_id += 1;
+        self.queue.push_back((new_id, waker.clone()));
+        *id = Some(new_id);
+    }
+
+    /// Removes the entry from the queue.
+    ///
+    /// If the entry was already woken up, the next entry is woken instead,
+    /// so a wake-up doesn't get lost if the waiting future is dropped.
+    pub(crate) fn cancel(&mut self, id: &mut Option<u64>) -> Option<Waker> {
+        let id = id.take()?;
+        match self.queue.iter().position(|(queued, _)| *queued == id) {
+            Some(idx) => {
+                self.queue.remove(idx);
+                None
+            }
+            None => self.notify_one(),
+        }
+    }
+
+    /// Removes the entry from the queue, without passing on a wake-up.
+    pub(crate) fn remove(&mut self, id: &mut Option<u64>) {
+        if let Some(id) = id.take() {
+            self.queue.retain(|(queued, _)| *queued != id);
+        }
+    }
+
+    /// Pops the entry at the front of the queue, and returns its waker.
+    pub(crate) fn notify_one(&mut self) -> Option<Waker> {
+        self.queue.pop_front().map(|(_, waker)| waker)
+    }
+
+    /// Pops all entries of the queue, and returns their wakers.
+    pub(crate) fn notify_all(&mut self) -> Vec<Waker> {
+        self.queue.drain(..).map(|(_, waker)| waker).collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
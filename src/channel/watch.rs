@@ -0,0 +1,302 @@
+//! A single-producer, multi-consumer channel that only retains the most recent value.
+//!
+//! Receivers can [borrow](Receiver::borrow) the current value at any time, and
+//! [wait](Receiver::changed) until a new value was sent. Values which are sent
+//! while a receiver doesn't look are never seen by it, so this is useful to
+//! broadcast configuration or state changes.
+
+use super::{block_on, wake_all, waiters::WaitQueue, PollFn};
+use futures_core::Stream;
+use std::{
+    error::Error,
+    fmt,
+    ops::Deref,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex, RwLock, RwLockReadGuard},
+    task::{Context, Poll},
+};
+
+/// Creates a watch channel with the initial value.
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        value: RwLock::new(init),
+        state: StdMutex::new(State {
+            version: 0,
+            closed: false,
+            receivers: 1,
+            waiters: WaitQueue::new(),
+        }),
+    });
+    let sender = Sender {
+        shared: shared.clone(),
+    };
+    let receiver = Receiver {
+        shared,
+        version: 0,
+        waiter: None,
+    };
+    (sender, receiver)
+}
+
+struct Shared<T> {
+    value: RwLock<T>,
+    state: StdMutex<State>,
+}
+
+struct State {
+    /// Incremented every time a new value is sent.
+    version: u64,
+    /// Set when the sender was dropped.
+    closed: bool,
+    receivers: usize,
+    waiters: WaitQueue,
+}
+
+/// A reference to the current value of the channel.
+///
+/// Holding it blocks the sender from sending new values, so it should be dropped quickly.
+pub struct Ref<'a, T>(RwLockReadGuard<'a, T>);
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Ref<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// The sending half of a watch channel.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends a new value, and notifies all receivers.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if there are no receivers.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if self.receiver_count() == 0 {
+            return Err(SendError(value));
+        }
+        self.send_replace(value);
+        Ok(())
+    }
+
+    /// Sends a new value, even if there are no receivers, and returns the previous value.
+    pub fn send_replace(&self, value: T) -> T {
+        let old = std::mem::replace(&mut *self.shared.value.write().unwrap(), value);
+
+        let wakers = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.version += 1;
+            state.waiters.notify_all()
+        };
+        wake_all(wakers);
+        old
+    }
+
+    /// Returns a reference to the current value.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref(self.shared.value.read().unwrap())
+    }
+
+    /// Creates a new receiver, which treats the current value as seen.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers += 1;
+        Receiver {
+            shared: self.shared.clone(),
+            version: state.version,
+            waiter: None,
+        }
+    }
+
+    /// Returns the number of receivers.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().unwrap().receivers
+    }
+
+    /// Returns `true` if all receivers were dropped.
+    pub fn is_closed(&self) -> bool {
+        self.receiver_count() == 0
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.closed = true;
+            state.waiters.notify_all()
+        };
+        wake_all(wakers);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("value", &*self.borrow())
+            .finish()
+    }
+}
+
+/// The receiving half of a watch channel.
+///
+/// The receiver can be cloned, the clone has seen the same values as the original.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    /// The version of the value that was seen last.
+    version: u64,
+    waiter: Option<u64>,
+}
+
+impl<T> Receiver<T> {
+    /// Returns a reference to the current value, without marking it as seen.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref(self.shared.value.read().unwrap())
+    }
+
+    /// Returns a reference to the current value, and marks it as seen.
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let value = self.shared.value.read().unwrap();
+        self.version = self.shared.state.lock().unwrap().version;
+        Ref(value)
+    }
+
+    /// Returns `true` if a value was sent, which wasn't seen by this receiver yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender was dropped.
+    pub fn has_changed(&self) -> Result<bool, RecvError> {
+        let state = self.shared.state.lock().unwrap();
+        if state.closed {
+            return Err(RecvError(()));
+        }
+        Ok(state.version != self.version)
+    }
+
+    /// Waits until a value was sent, which wasn't seen by this receiver yet,
+    /// and marks it as seen.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender was dropped.
+    pub async fn changed(&mut self) -> Result<(), RecvError> {
+        PollFn(|cx: &mut Context<'_>| self.poll_changed(cx)).await
+    }
+
+    /// Blocks the current thread until a value was sent, which wasn't seen by
+    /// this receiver yet, and marks it as seen.
+    ///
+    /// This must not be called from within an asynchronous task.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender was dropped.
+    pub fn changed_blocking(&mut self) -> Result<(), RecvError> {
+        block_on(self.changed())
+    }
+
+    /// Polls whether a value was sent, which wasn't seen by this receiver yet,
+    /// and marks it as seen.
+    pub fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), RecvError>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.version != self.version {
+            state.waiters.remove(&mut self.waiter);
+            self.version = state.version;
+            return Poll::Ready(Ok(()));
+        }
+        if state.closed {
+            state.waiters.remove(&mut self.waiter);
+            return Poll::Ready(Err(RecvError(())));
+        }
+
+        state.waiters.register(&mut self.waiter, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().receivers += 1;
+        Self {
+            shared: self.shared.clone(),
+            version: self.version,
+            waiter: None,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers -= 1;
+        state.waiters.remove(&mut self.waiter);
+    }
+}
+
+/// The stream yields a clone of every value which wasn't seen yet,
+/// and ends when the sender was dropped.
+impl<T: Clone> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        match this.poll_changed(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(Some(this.borrow().clone())),
+            Poll::Ready(Err(_)) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("value", &*self.borrow())
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`Sender::send`] if there are no receivers.
+///
+/// It contains the value that couldn't be sent.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("SendError { .. }")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel closed")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`Receiver::changed`] if the sender was dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("channel closed")
+    }
+}
+
+impl Error for RecvError {}
@@ -0,0 +1,246 @@
+//! Inspection of the tasks that are alive, to debug hangs.
+//!
+//! With the `task_dump` feature, every task spawned through agnostik is recorded in a
+//! registry until it finishes. [`dump_tasks`] returns a snapshot of the registry, which
+//! shows how long each task is alive, how often it was polled, and when it was last polled.
+//! A task that wasn't polled for a long time is usually waiting on something that never
+//! happens.
+//!
+//! ```ignore
+//! // print a dump to stderr whenever the process receives `SIGUSR1`
+//! agnostik::debug::dump_on_signal()?;
+//!
+//! for task in agnostik::debug::dump_tasks().tasks() {
+//!     println!("{} was polled {} times", task.task().id(), task.polls());
+//! }
+//! ```
+
+use crate::task::{Id, Task};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::{Duration, Instant},
+};
+
+static REGISTRY: Lazy<StdMutex<HashMap<Id, Arc<Entry>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// A task in the registry.
+struct Entry {
+    task: Task,
+    kind: &'static str,
+    spawned: Instant,
+    polls: AtomicU64,
+    /// Nanoseconds between spawning the task and the start of its last poll, plus one,
+    /// or zero if it was never polled.
+    last_poll: AtomicU64,
+    running: AtomicBool,
+}
+
+/// Keeps a task in the registry, until it's dropped.
+pub(crate) struct Registration(Arc<Entry>);
+
+impl Registration {
+    pub(crate) fn new(task: &Task, kind: &'static str) -> Self {
+        let entry = Arc::new(Entry {
+            task: task.clone(),
+            kind,
+            spawned: Instant::now(),
+            polls: AtomicU64::new(0),
+            last_poll: AtomicU64::new(0),
+            running: AtomicBool::new(false),
+        });
+        REGISTRY
+            .lock()
+            .unwrap()
+            .insert(task.id(), Arc::clone(&entry));
+        Self(entry)
+    }
+
+    /// Marks the task as running while the closure runs.
+    pub(crate) fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        /// Marks the task as idle, even if the closure panics.
+        struct Reset<'a>(&'a Entry);
+
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                self.0.running.store(false, Ordering::Relaxed);
+            }
+        }
+
+        let entry = &*self.0;
+        let since_spawn = u64::try_from(entry.spawned.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        entry.polls.fetch_add(1, Ordering::Relaxed);
+        entry
+            .last_poll
+            .store(since_spawn.saturating_add(1), Ordering::Relaxed);
+        entry.running.store(true, Ordering::Relaxed);
+
+        let _reset = Reset(entry);
+        f()
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        REGISTRY.lock().unwrap().remove(&self.0.task.id());
+    }
+}
+
+/// Returns a snapshot of all tasks that were spawned through agnostik, and didn't finish yet,
+/// sorted by their id.
+pub fn dump_tasks() -> TaskDump {
+    let now = Instant::now();
+    let mut tasks = REGISTRY
+        .lock()
+        .unwrap()
+        .values()
+        .map(|entry| {
+            let last_poll = match entry.last_poll.load(Ordering::Relaxed) {
+                0 => None,
+                nanos => Some(entry.spawned + Duration::from_nanos(nanos - 1)),
+            };
+            TaskInfo {
+                task: entry.task.clone(),
+                kind: entry.kind,
+                age: now.saturating_duration_since(entry.spawned),
+                polls: entry.polls.load(Ordering::Relaxed),
+                idle: last_poll.map(|last_poll| now.saturating_duration_since(last_poll)),
+                running: entry.running.load(Ordering::Relaxed),
+            }
+        })
+        .collect::<Vec<_>>();
+    tasks.sort_by_key(|task| task.task.id());
+    TaskDump { tasks }
+}
+
+/// Prints a [dump](dump_tasks) to stderr whenever the process receives `SIGUSR1`.
+///
+/// The dump is printed by a background thread, which is started by the first call.
+///
+/// # Errors
+///
+/// Returns an error if the signal handler couldn't be registered.
+#[cfg(unix)]
+pub fn dump_on_signal() -> std::io::Result<()> {
+    use signal_hook::{consts::SIGUSR1, iterator::Signals};
+    use std::thread;
+
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let mut signals = match Signals::new([SIGUSR1]) {
+        Ok(signals) => signals,
+        Err(err) => {
+            INSTALLED.store(false, Ordering::SeqCst);
+            return Err(err);
+        }
+    };
+    thread::Builder::new()
+        .name("agnostik-task-dump".into())
+        .spawn(move || {
+            for _ in signals.forever() {
+                eprint!("{}", dump_tasks());
+            }
+        })?;
+    Ok(())
+}
+
+/// A snapshot of the tasks that are alive, returned by [`dump_tasks`].
+///
+/// The `Display` implementation prints one task per line.
+#[derive(Clone, Debug)]
+pub struct TaskDump {
+    tasks: Vec<TaskInfo>,
+}
+
+impl TaskDump {
+    /// Returns the tasks, sorted by their id.
+    pub fn tasks(&self) -> &[TaskInfo] {
+        &self.tasks
+    }
+}
+
+impl fmt::Display for TaskDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} tasks alive", self.tasks.len())?;
+        for task in &self.tasks {
+            writeln!(f, "{}", task)?;
+        }
+        Ok(())
+    }
+}
+
+/// Information about a single task in a [`TaskDump`].
+#[derive(Clone, Debug)]
+pub struct TaskInfo {
+    task: Task,
+    kind: &'static str,
+    age: Duration,
+    polls: u64,
+    idle: Option<Duration>,
+    running: bool,
+}
+
+impl TaskInfo {
+    /// Returns the task, which has the id, name and spawn location of the task.
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    /// Returns the kind of the task, which is `async`, `blocking` or `local`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Returns how long ago the task was spawned.
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    /// Returns how often the task was polled. Blocking tasks count as polled once they run.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    /// Returns how long ago the last poll of the task started, or `None` if it was never polled.
+    pub fn since_last_poll(&self) -> Option<Duration> {
+        self.idle
+    }
+
+    /// Returns `true` if the task was being polled when the dump was taken.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+impl fmt::Display for TaskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task {}", self.task.id())?;
+        if let Some(name) = self.task.name() {
+            write!(f, " '{}'", name)?;
+        }
+        write!(
+            f,
+            " ({}) spawned at {}, age {:?}, {} polls",
+            self.kind,
+            self.task.location(),
+            self.age,
+            self.polls
+        )?;
+        match self.idle {
+            _ if self.running => write!(f, ", running"),
+            Some(idle) => write!(f, ", last polled {:?} ago", idle),
+            None => write!(f, ", never polled"),
+        }
+    }
+}
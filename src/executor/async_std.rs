@@ -1,6 +1,7 @@
 //! The async std executor
 
 use crate::join_handle::{InnerJoinHandle, JoinHandle};
+use crate::task::{Task, TaskFuture};
 use crate::{AgnostikExecutor, LocalAgnostikExecutor};
 use async_std_crate as async_std;
 use std::future::Future;
@@ -9,30 +10,77 @@ use std::future::Future;
 /// `LocalAgnostikExecutor`.
 pub struct AsyncStdExecutor;
 
+#[allow(clippy::unused_self)]
 impl AsyncStdExecutor {
     /// Create a new `AsyncStdExecutor`.
     pub const fn new() -> Self {
         AsyncStdExecutor {}
     }
+
+    /// Returns a task builder, which passes the name of the task to async-std.
+    fn builder(task: &Task) -> async_std::task::Builder {
+        let builder = async_std::task::Builder::new();
+        match task.name() {
+            Some(name) => builder.name(name.to_owned()),
+            None => builder,
+        }
+    }
+
+    /// Spawns the future as the given task.
+    pub(crate) fn spawn_task<F>(&self, task: Task, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let id = task.id();
+        let handle = Self::builder(&task)
+            .spawn(TaskFuture::new(task, future))
+            .expect("cannot spawn task");
+        JoinHandle::new(id, InnerJoinHandle::AsyncStd(handle))
+    }
+
+    /// Runs the closure on a thread for blocking tasks, as the given task.
+    pub(crate) fn spawn_blocking_task<F, T>(&self, task: Task, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let id = task.id();
+        let handle = async_std::task::spawn_blocking(TaskFuture::blocking(task, f));
+        JoinHandle::new(id, InnerJoinHandle::AsyncStd(handle))
+    }
+
+    /// Spawns the `!Send` future as the given task.
+    pub(crate) fn spawn_local_task<F>(&self, task: Task, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let id = task.id();
+        let handle = Self::builder(&task)
+            .local(TaskFuture::local(task, future))
+            .expect("cannot spawn task");
+        JoinHandle::new(id, InnerJoinHandle::AsyncStd(handle))
+    }
 }
 
 impl AgnostikExecutor for AsyncStdExecutor {
+    #[track_caller]
     fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        let handle = async_std::task::spawn(future);
-        JoinHandle(InnerJoinHandle::AsyncStd(handle))
+        self.spawn_task(Task::new(None), future)
     }
 
+    #[track_caller]
     fn spawn_blocking<F, T>(&self, task: F) -> JoinHandle<T>
     where
         F: FnOnce() -> T + Send + 'static,
         T: Send + 'static,
     {
-        let handle = async_std::task::spawn_blocking(task);
-        JoinHandle(InnerJoinHandle::AsyncStd(handle))
+        self.spawn_blocking_task(Task::new(None), task)
     }
 
     fn block_on<F>(&self, future: F) -> F::Output
@@ -45,12 +93,12 @@ impl AgnostikExecutor for AsyncStdExecutor {
 }
 
 impl LocalAgnostikExecutor for AsyncStdExecutor {
+    #[track_caller]
     fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output>
     where
         F: Future + 'static,
         F::Output: 'static,
     {
-        let handle = async_std::task::spawn_local(future);
-        JoinHandle(InnerJoinHandle::AsyncStd(handle))
+        self.spawn_local_task(Task::new(None), future)
     }
 }
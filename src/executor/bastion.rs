@@ -1,6 +1,7 @@
 //! The bastion executor.
 
 use crate::join_handle::{InnerJoinHandle, JoinHandle};
+use crate::task::{Joinable, Task, TaskFuture};
 use crate::AgnostikExecutor;
 use lightproc::prelude::*;
 use std::future::Future;
@@ -9,30 +10,82 @@ use std::future::Future;
 /// and can be used to spawn and run futures using the bastion executor.
 pub struct BastionExecutor;
 
+#[allow(clippy::unused_self)]
 impl BastionExecutor {
     /// Create a new `BastionExecutor` instance.
     pub const fn new() -> Self {
         BastionExecutor {}
     }
+
+    /// Spawns the future using the given `ProcStack`, which allows to set a pid and to
+    /// register the callbacks of lightproc.
+    #[track_caller]
+    pub fn spawn_with_stack<F>(&self, stack: ProcStack, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_task_with_stack(Task::new(None), stack, future)
+    }
+
+    /// Spawns the future as the given task.
+    pub(crate) fn spawn_task<F>(&self, task: Task, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_task_with_stack(task, ProcStack::default(), future)
+    }
+
+    fn spawn_task_with_stack<F>(
+        &self,
+        task: Task,
+        stack: ProcStack,
+        future: F,
+    ) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let id = task.id();
+        let handle =
+            bastion_executor::pool::spawn(Joinable::new(TaskFuture::new(task, future)), stack);
+        JoinHandle::new(id, InnerJoinHandle::Bastion(handle))
+    }
+
+    /// Runs the closure on a thread for blocking tasks, as the given task.
+    pub(crate) fn spawn_blocking_task<F, T>(&self, task: Task, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let id = task.id();
+        let f = TaskFuture::blocking(task, f);
+        let handle = bastion_executor::pool::spawn_blocking(
+            Joinable::new(async move { f() }),
+            ProcStack::default(),
+        );
+        JoinHandle::new(id, InnerJoinHandle::Bastion(handle))
+    }
 }
 
 impl AgnostikExecutor for BastionExecutor {
+    #[track_caller]
     fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        let handle = bastion_executor::pool::spawn(future, ProcStack::default());
-        JoinHandle(InnerJoinHandle::Bastion(handle))
+        self.spawn_task(Task::new(None), future)
     }
 
+    #[track_caller]
     fn spawn_blocking<F, T>(&self, task: F) -> JoinHandle<T>
     where
         F: FnOnce() -> T + Send + 'static,
         T: Send + 'static,
     {
-        let handle = bastion_executor::pool::spawn_blocking(async { task() }, ProcStack::default());
-        JoinHandle(InnerJoinHandle::Bastion(handle))
+        self.spawn_blocking_task(Task::new(None), task)
     }
 
     fn block_on<F>(&self, future: F) -> F::Output
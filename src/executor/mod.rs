@@ -25,3 +25,22 @@ pub use tokio1::*;
 mod smol;
 #[cfg(smol)]
 pub use smol::*;
+
+/// The name of the runtime that is used by the global executor.
+#[cfg(bastion)]
+pub const BACKEND: &str = "bastion";
+/// The name of the runtime that is used by the global executor.
+#[cfg(async_std)]
+pub const BACKEND: &str = "async-std";
+/// The name of the runtime that is used by the global executor.
+#[cfg(tokio)]
+pub const BACKEND: &str = "tokio 0.3";
+/// The name of the runtime that is used by the global executor.
+#[cfg(tokio1)]
+pub const BACKEND: &str = "tokio 1";
+/// The name of the runtime that is used by the global executor.
+#[cfg(smol)]
+pub const BACKEND: &str = "smol";
+/// The name of the runtime that is used by the global executor.
+#[cfg(not(enable))]
+pub const BACKEND: &str = "none";
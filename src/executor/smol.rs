@@ -1,6 +1,7 @@
 use smol_crate as smol;
 
 use crate::join_handle::{InnerJoinHandle, JoinHandle};
+use crate::task::{Task, TaskFuture};
 use crate::AgnostikExecutor;
 use std::future::Future;
 
@@ -8,30 +9,53 @@ use std::future::Future;
 /// `LocalAgnostikExecutor`.
 pub struct SmolExecutor;
 
+#[allow(clippy::unused_self)]
 impl SmolExecutor {
     /// Create a new `SmolExecutor`.
     pub const fn new() -> Self {
         Self
     }
+
+    /// Spawns the future as the given task.
+    pub(crate) fn spawn_task<F>(&self, task: Task, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let id = task.id();
+        let task = smol::spawn(TaskFuture::new(task, future));
+        JoinHandle::new(id, InnerJoinHandle::Smol(task))
+    }
+
+    /// Runs the closure on a thread for blocking tasks, as the given task.
+    pub(crate) fn spawn_blocking_task<F, T>(&self, task: Task, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let id = task.id();
+        let task = smol::spawn(smol::unblock(TaskFuture::blocking(task, f)));
+        JoinHandle::new(id, InnerJoinHandle::Smol(task))
+    }
 }
 
 impl AgnostikExecutor for SmolExecutor {
+    #[track_caller]
     fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        let task = smol::spawn(future);
-        JoinHandle(InnerJoinHandle::Smol(task))
+        self.spawn_task(Task::new(None), future)
     }
 
+    #[track_caller]
     fn spawn_blocking<F, T>(&self, task: F) -> JoinHandle<T>
     where
         F: FnOnce() -> T + Send + 'static,
         T: Send + 'static,
     {
-        let task = smol::spawn(smol::unblock(|| task()));
-        JoinHandle(InnerJoinHandle::Smol(task))
+        self.spawn_blocking_task(Task::new(None), task)
     }
 
     fn block_on<F>(&self, future: F) -> F::Output
@@ -1,13 +1,16 @@
 use crate::join_handle::{InnerJoinHandle, JoinHandle};
+use crate::task::{Task, TaskFuture};
 use crate::{AgnostikExecutor, LocalAgnostikExecutor};
 use std::future::Future;
 use std::sync::Mutex;
+use std::time::Duration;
 use tokio1_crate as tokio;
 
 /// A wrapper around the `tokio` (version 1.*) crate which implements `AgnostikExecutor` and
 /// `LocalAgnostikExecutor`.
-pub struct Tokio1Executor(Mutex<tokio::runtime::Runtime>);
+pub struct Tokio1Executor(Mutex<Option<tokio::runtime::Runtime>>);
 
+#[allow(clippy::unused_self)]
 impl Tokio1Executor {
     /// Create a new `Tokio1Executor`.
     pub fn new() -> Self {
@@ -16,32 +19,79 @@ impl Tokio1Executor {
 
     /// Create a new `TokioExecutor` with a custom runtime.
     pub fn with_runtime(runtime: tokio::runtime::Runtime) -> Self {
-        Tokio1Executor(Mutex::new(runtime))
+        Tokio1Executor(Mutex::new(Some(runtime)))
     }
 
     pub(crate) fn set_runtime(&self, runtime: tokio::runtime::Runtime) {
         let mut inner = self.0.lock().unwrap();
-        *inner = runtime;
+        *inner = Some(runtime);
+    }
+
+    /// Shuts down the runtime, unless it's currently used to block on a future.
+    pub(crate) fn shutdown_runtime(&self, timeout: Duration) {
+        let runtime = match self.0.try_lock() {
+            Ok(mut inner) => inner.take(),
+            Err(_) => None,
+        };
+
+        if let Some(runtime) = runtime {
+            runtime.shutdown_timeout(timeout);
+        }
+    }
+
+    /// Spawns the future as the given task.
+    pub(crate) fn spawn_task<F>(&self, task: Task, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let id = task.id();
+        let handle = tokio::task::spawn(TaskFuture::new(task, future));
+        JoinHandle::new(id, InnerJoinHandle::Tokio1(handle))
+    }
+
+    /// Runs the closure on a thread for blocking tasks, as the given task.
+    pub(crate) fn spawn_blocking_task<F, T>(&self, task: Task, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let id = task.id();
+        #[cfg(target_os = "linux")]
+        let f = crate::affinity::global_blocking(f);
+        let handle = tokio::task::spawn_blocking(TaskFuture::blocking(task, f));
+        JoinHandle::new(id, InnerJoinHandle::Tokio1(handle))
+    }
+
+    /// Spawns the `!Send` future as the given task.
+    pub(crate) fn spawn_local_task<F>(&self, task: Task, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let id = task.id();
+        let handle = tokio::task::spawn_local(TaskFuture::local(task, future));
+        JoinHandle::new(id, InnerJoinHandle::Tokio1(handle))
     }
 }
 
 impl AgnostikExecutor for Tokio1Executor {
+    #[track_caller]
     fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        let handle = tokio::task::spawn(future);
-        JoinHandle(InnerJoinHandle::Tokio1(handle))
+        self.spawn_task(Task::new(None), future)
     }
 
+    #[track_caller]
     fn spawn_blocking<F, T>(&self, task: F) -> JoinHandle<T>
     where
         F: FnOnce() -> T + Send + 'static,
         T: Send + 'static,
     {
-        let handle = tokio::task::spawn_blocking(task);
-        JoinHandle(InnerJoinHandle::Tokio1(handle))
+        self.spawn_blocking_task(Task::new(None), task)
     }
 
     fn block_on<F>(&self, future: F) -> F::Output
@@ -49,17 +99,22 @@ impl AgnostikExecutor for Tokio1Executor {
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        self.0.lock().unwrap().block_on(future)
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .expect("the tokio runtime was shut down")
+            .block_on(future)
     }
 }
 
 impl LocalAgnostikExecutor for Tokio1Executor {
+    #[track_caller]
     fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output>
     where
         F: Future + 'static,
         F::Output: 'static,
     {
-        let handle = tokio::task::spawn_local(future);
-        JoinHandle(InnerJoinHandle::Tokio1(handle))
+        self.spawn_local_task(Task::new(None), future)
     }
 }
@@ -0,0 +1,185 @@
+//! Asynchronous adapter for blocking I/O handles.
+
+use crate::join_handle::JoinHandle;
+use std::{
+    future::Future,
+    io::{self, Read, Write},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// The maximum amount of bytes that are moved to the blocking thread at once.
+const CHUNK_SIZE: usize = super::BUF_SIZE;
+
+/// Runs the I/O operations of a blocking handle on the blocking thread pool
+/// of the global executor.
+///
+/// The handle is expected to be used either for reading or for writing, not both.
+/// Writes are performed in the background, so errors that occur during a write
+/// will be reported by the next call to `poll_write` or `poll_flush`.
+pub(crate) struct Blocking<T> {
+    state: State<T>,
+    error: Option<io::Error>,
+}
+
+enum State<T> {
+    Idle(Option<Box<Inner<T>>>),
+    Busy(Pin<Box<Task<T>>>),
+}
+
+type Task<T> = JoinHandle<(Box<Inner<T>>, Op)>;
+
+struct Inner<T> {
+    io: T,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+/// The operation that has been executed on the blocking thread.
+enum Op {
+    Read(io::Result<usize>),
+    Write(io::Result<()>),
+    Flush(io::Result<()>),
+}
+
+impl<T> Blocking<T> {
+    pub(crate) fn new(io: T) -> Self {
+        let inner = Inner {
+            io,
+            buf: Vec::new(),
+            pos: 0,
+        };
+
+        Self {
+            state: State::Idle(Some(Box::new(inner))),
+            error: None,
+        }
+    }
+}
+
+impl<T: Send + 'static> Blocking<T> {
+    // without a runtime, `spawn_blocking` returns an uninhabited `JoinHandle`
+    #[cfg_attr(not(enable), allow(unreachable_code))]
+    fn spawn<F>(&mut self, op: F)
+    where
+        F: FnOnce(&mut Inner<T>) -> Op + Send + 'static,
+    {
+        let mut inner = match &mut self.state {
+            State::Idle(inner) => inner.take().expect("blocking handle is in an invalid state"),
+            State::Busy(_) => unreachable!("spawned operation while another one is running"),
+        };
+
+        self.state = State::Busy(Box::pin(crate::spawn_blocking(move || {
+            let op = op(&mut inner);
+            (inner, op)
+        })));
+    }
+
+    /// Waits for the running operation to finish, and returns its result if
+    /// it was a read or flush.
+    ///
+    /// Errors of background writes are stored, and reported later.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<Option<Op>> {
+        let handle = match &mut self.state {
+            State::Idle(_) => return Poll::Ready(None),
+            State::Busy(handle) => handle,
+        };
+
+        let (inner, op) = futures_core::ready!(handle.as_mut().poll(cx));
+        self.state = State::Idle(Some(inner));
+        match op {
+            Op::Write(Err(err)) => {
+                self.error = Some(err);
+                Poll::Ready(None)
+            }
+            Op::Write(Ok(())) => Poll::Ready(None),
+            op => Poll::Ready(Some(op)),
+        }
+    }
+
+    fn inner(&mut self) -> &mut Inner<T> {
+        match &mut self.state {
+            State::Idle(Some(inner)) => inner,
+            _ => unreachable!("blocking handle is not idle"),
+        }
+    }
+}
+
+impl<T> futures_io::AsyncRead for Blocking<T>
+where
+    T: Read + Send + 'static,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match futures_core::ready!(self.poll_idle(cx)) {
+                Some(Op::Read(Ok(0))) => return Poll::Ready(Ok(0)),
+                Some(Op::Read(Err(err))) => return Poll::Ready(Err(err)),
+                _ => {}
+            }
+
+            let inner = self.inner();
+            if inner.pos < inner.buf.len() {
+                let available = &inner.buf[inner.pos..];
+                let len = available.len().min(buf.len());
+                buf[..len].copy_from_slice(&available[..len]);
+                inner.pos += len;
+                return Poll::Ready(Ok(len));
+            }
+
+            let len = buf.len().min(CHUNK_SIZE);
+            self.spawn(move |inner| {
+                inner.buf.resize(len, 0);
+                inner.pos = 0;
+
+                let res = inner.io.read(&mut inner.buf);
+                inner.buf.truncate(*res.as_ref().unwrap_or(&0));
+                Op::Read(res)
+            });
+        }
+    }
+}
+
+impl<T> futures_io::AsyncWrite for Blocking<T>
+where
+    T: Write + Send + 'static,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        futures_core::ready!(self.poll_idle(cx));
+        if let Some(err) = self.error.take() {
+            return Poll::Ready(Err(err));
+        }
+
+        let len = buf.len().min(CHUNK_SIZE);
+        let inner = self.inner();
+        inner.buf.clear();
+        inner.buf.extend_from_slice(&buf[..len]);
+
+        self.spawn(|inner| Op::Write(inner.io.write_all(&inner.buf)));
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            if let Some(Op::Flush(res)) = futures_core::ready!(self.poll_idle(cx)) {
+                return Poll::Ready(res);
+            }
+            if let Some(err) = self.error.take() {
+                return Poll::Ready(Err(err));
+            }
+
+            self.spawn(|inner| Op::Flush(inner.io.flush()));
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
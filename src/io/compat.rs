@@ -0,0 +1,62 @@
+//! Adapter that implements the `futures-io` traits for tokio's I/O types.
+
+#[cfg(tokio)]
+pub(crate) use tokio_crate as tokio;
+#[cfg(tokio1)]
+pub(crate) use tokio1_crate as tokio;
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Wraps a type that implements tokio's `AsyncRead` and/or `AsyncWrite`,
+/// and implements the corresponding `futures-io` traits for it.
+pub(crate) struct Compat<T>(T);
+
+impl<T> Compat<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Compat(inner)
+    }
+
+    pub(crate) fn get_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> futures_io::AsyncRead for Compat<T>
+where
+    T: tokio::io::AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+        futures_core::ready!(Pin::new(&mut self.0).poll_read(cx, &mut buf))?;
+        Poll::Ready(Ok(buf.filled().len()))
+    }
+}
+
+impl<T> futures_io::AsyncWrite for Compat<T>
+where
+    T: tokio::io::AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
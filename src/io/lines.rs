@@ -0,0 +1,162 @@
+//! Line-oriented reading helpers.
+
+use futures_core::Stream;
+use futures_io::AsyncBufRead;
+use std::{
+    future::Future,
+    io, mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Future for the [`read_line`](super::Stdin::read_line) method.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct ReadLine<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buf: &'a mut String,
+    bytes: Vec<u8>,
+    start: usize,
+    read: usize,
+}
+
+impl<'a, R: ?Sized> ReadLine<'a, R> {
+    pub(super) fn new(reader: &'a mut R, buf: &'a mut String) -> Self {
+        Self {
+            reader,
+            start: buf.len(),
+            bytes: mem::take(buf).into_bytes(),
+            buf,
+            read: 0,
+        }
+    }
+}
+
+impl<R: AsyncBufRead + ?Sized + Unpin> Future for ReadLine<'_, R> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Self {
+            reader,
+            buf,
+            bytes,
+            start,
+            read,
+        } = self.get_mut();
+
+        let res = futures_core::ready!(read_until_newline(Pin::new(reader), cx, bytes, read));
+        match String::from_utf8(mem::take(bytes)) {
+            Ok(line) => {
+                **buf = line;
+                Poll::Ready(res)
+            }
+            Err(err) => {
+                // restore the previous content of the buffer, and drop the invalid data
+                let mut valid = err.into_bytes();
+                valid.truncate(*start);
+                **buf = String::from_utf8(valid).expect("buffer contained valid UTF-8 before");
+                Poll::Ready(res.and_then(|_| {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8",
+                    ))
+                }))
+            }
+        }
+    }
+}
+
+/// Stream over the lines of a reader, created by the [`lines`](super::Stdin::lines) method.
+///
+/// The yielded lines don't contain the trailing newline (`\n` or `\r\n`).
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Lines<R> {
+    reader: R,
+    bytes: Vec<u8>,
+    read: usize,
+}
+
+impl<R> Lines<R> {
+    pub(super) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            bytes: Vec::new(),
+            read: 0,
+        }
+    }
+
+    /// Returns the underlying reader.
+    ///
+    /// Data of a partially read line is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Self {
+            reader,
+            bytes,
+            read,
+        } = self.get_mut();
+
+        let n = futures_core::ready!(read_until_newline(Pin::new(reader), cx, bytes, read))?;
+        if n == 0 && bytes.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        if bytes.ends_with(b"\n") {
+            bytes.pop();
+            if bytes.ends_with(b"\r") {
+                bytes.pop();
+            }
+        }
+
+        let line = String::from_utf8(mem::take(bytes)).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        });
+        Poll::Ready(Some(line))
+    }
+}
+
+/// Appends bytes to `bytes` until a newline is found, or the reader reached EOF.
+///
+/// `read` keeps track of the bytes appended so far, which makes this function
+/// resumable across multiple polls.
+fn read_until_newline<R: AsyncBufRead + ?Sized>(
+    mut reader: Pin<&mut R>,
+    cx: &mut Context<'_>,
+    bytes: &mut Vec<u8>,
+    read: &mut usize,
+) -> Poll<io::Result<usize>> {
+    loop {
+        let (done, used) = {
+            let available = match futures_core::ready!(reader.as_mut().poll_fill_buf(cx)) {
+                Ok(available) => available,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Poll::Ready(Err(err)),
+            };
+
+            if let Some(idx) = available.iter().position(|&b| b == b'\n') {
+                bytes.extend_from_slice(&available[..=idx]);
+                (true, idx + 1)
+            } else {
+                bytes.extend_from_slice(available);
+                (available.is_empty(), available.len())
+            }
+        };
+
+        reader.as_mut().consume(used);
+        *read += used;
+        if done {
+            return Poll::Ready(Ok(mem::replace(read, 0)));
+        }
+    }
+}
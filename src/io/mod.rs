@@ -0,0 +1,221 @@
+//! Asynchronous handles to the standard streams.
+//!
+//! The handles returned by [`stdin`], [`stdout`] and [`stderr`] implement the
+//! [`AsyncRead`], [`AsyncBufRead`] and [`AsyncWrite`] traits from the `futures-io` crate,
+//! independent of the runtime feature that is enabled.
+//!
+//! The tokio and async-std runtimes provide their own implementations, which are used
+//! directly. For smol and bastion, the blocking standard streams are driven by
+//! [`spawn_blocking`](crate::spawn_blocking) tasks.
+//!
+//! ```ignore
+//! use agnostik::io;
+//! use futures_util::io::AsyncWriteExt;
+//!
+//! agnostik::block_on(async {
+//!     let mut stdin = io::stdin();
+//!     let mut stdout = io::stdout();
+//!
+//!     let mut line = String::new();
+//!     while stdin.read_line(&mut line).await? != 0 {
+//!         stdout.write_all(line.to_uppercase().as_bytes()).await?;
+//!         line.clear();
+//!     }
+//!     stdout.flush().await
+//! })
+//! ```
+
+#[cfg(not(any(tokio, tokio1, async_std)))]
+pub(crate) mod blocking;
+#[cfg(any(tokio, tokio1))]
+pub(crate) mod compat;
+mod lines;
+
+pub use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+pub use lines::{Lines, ReadLine};
+
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(any(tokio, tokio1))]
+type StdinInner = compat::Compat<compat::tokio::io::Stdin>;
+#[cfg(any(tokio, tokio1))]
+type StdoutInner = compat::Compat<compat::tokio::io::Stdout>;
+#[cfg(any(tokio, tokio1))]
+type StderrInner = compat::Compat<compat::tokio::io::Stderr>;
+
+#[cfg(async_std)]
+type StdinInner = async_std_crate::io::Stdin;
+#[cfg(async_std)]
+type StdoutInner = async_std_crate::io::Stdout;
+#[cfg(async_std)]
+type StderrInner = async_std_crate::io::Stderr;
+
+#[cfg(not(any(tokio, tokio1, async_std)))]
+type StdinInner = blocking::Blocking<io::Stdin>;
+#[cfg(not(any(tokio, tokio1, async_std)))]
+type StdoutInner = blocking::Blocking<io::Stdout>;
+#[cfg(not(any(tokio, tokio1, async_std)))]
+type StderrInner = blocking::Blocking<io::Stderr>;
+
+/// The size of the buffer that is used by [`Stdin`].
+const BUF_SIZE: usize = 8 * 1024;
+
+/// Constructs a new handle to the standard input of the current process.
+///
+/// The returned handle is buffered, which makes it possible to read
+/// the input line by line using [`Stdin::read_line`] or [`Stdin::lines`].
+///
+/// **Note:** Every handle has its own buffer, so reading from multiple
+/// handles at the same time may interleave the input in unexpected ways.
+pub fn stdin() -> Stdin {
+    #[cfg(any(tokio, tokio1))]
+    let inner = compat::Compat::new(compat::tokio::io::stdin());
+    #[cfg(async_std)]
+    let inner = async_std_crate::io::stdin();
+    #[cfg(not(any(tokio, tokio1, async_std)))]
+    let inner = blocking::Blocking::new(io::stdin());
+
+    Stdin {
+        inner,
+        buf: vec![0; BUF_SIZE].into_boxed_slice(),
+        pos: 0,
+        cap: 0,
+    }
+}
+
+/// Constructs a new handle to the standard output of the current process.
+pub fn stdout() -> Stdout {
+    #[cfg(any(tokio, tokio1))]
+    let inner = compat::Compat::new(compat::tokio::io::stdout());
+    #[cfg(async_std)]
+    let inner = async_std_crate::io::stdout();
+    #[cfg(not(any(tokio, tokio1, async_std)))]
+    let inner = blocking::Blocking::new(io::stdout());
+
+    Stdout(inner)
+}
+
+/// Constructs a new handle to the standard error of the current process.
+pub fn stderr() -> Stderr {
+    #[cfg(any(tokio, tokio1))]
+    let inner = compat::Compat::new(compat::tokio::io::stderr());
+    #[cfg(async_std)]
+    let inner = async_std_crate::io::stderr();
+    #[cfg(not(any(tokio, tokio1, async_std)))]
+    let inner = blocking::Blocking::new(io::stderr());
+
+    Stderr(inner)
+}
+
+/// A buffered handle to the standard input of the current process.
+///
+/// Created by the [`stdin`] function.
+pub struct Stdin {
+    inner: StdinInner,
+    buf: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+}
+
+impl Stdin {
+    /// Reads all bytes until a newline (the `0xA` byte) is reached, and appends
+    /// them to the provided buffer.
+    ///
+    /// The newline is included in the appended data. If the returned future resolves
+    /// to `Ok(0)`, the end of the input has been reached.
+    pub fn read_line<'a>(&'a mut self, buf: &'a mut String) -> ReadLine<'a, Self> {
+        ReadLine::new(self, buf)
+    }
+
+    /// Converts this handle into a stream over the lines of the standard input.
+    ///
+    /// The yielded lines don't contain the trailing newline (`\n` or `\r\n`).
+    pub fn lines(self) -> Lines<Self> {
+        Lines::new(self)
+    }
+}
+
+impl AsyncRead for Stdin {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // bypass the internal buffer if it's empty and the read is large enough
+        if self.pos == self.cap && buf.len() >= self.buf.len() {
+            return Pin::new(&mut self.inner).poll_read(cx, buf);
+        }
+
+        let available = futures_core::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncBufRead for Stdin {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.pos == this.cap {
+            this.cap = futures_core::ready!(Pin::new(&mut this.inner).poll_read(cx, &mut this.buf))?;
+            this.pos = 0;
+        }
+        Poll::Ready(Ok(&this.buf[this.pos..this.cap]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.pos = (self.pos + amt).min(self.cap);
+    }
+}
+
+impl fmt::Debug for Stdin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Stdin { .. }")
+    }
+}
+
+/// A handle to the standard output of the current process.
+///
+/// Created by the [`stdout`] function.
+pub struct Stdout(StdoutInner);
+
+/// A handle to the standard error of the current process.
+///
+/// Created by the [`stderr`] function.
+pub struct Stderr(StderrInner);
+
+macro_rules! impl_write {
+    ($ty:ident) => {
+        impl AsyncWrite for $ty {
+            fn poll_write(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                Pin::new(&mut self.0).poll_write(cx, buf)
+            }
+
+            fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.0).poll_flush(cx)
+            }
+
+            fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.0).poll_close(cx)
+            }
+        }
+
+        impl fmt::Debug for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.pad(concat!(stringify!($ty), " { .. }"))
+            }
+        }
+    };
+}
+
+impl_write!(Stdout);
+impl_write!(Stderr);
@@ -1,5 +1,6 @@
 //! Generic join handle type.
 
+use crate::task::{Id, JoinError};
 use std::{
     convert::Infallible,
     future::Future,
@@ -24,16 +25,81 @@ use tokio_crate::task::JoinHandle as TokioHandle;
 ///
 /// **Note:** If you are using the bastion or tokio executor,
 /// agnostik will panic if the task failed to execute.
+/// Use [`try_join`](JoinHandle::try_join) to get an error instead.
 #[pin_project::pin_project]
 pub struct JoinHandle<R>(#[pin] pub InnerJoinHandle<R>);
 
+impl<R> JoinHandle<R> {
+    pub(crate) fn new(id: Id, inner: InnerJoinHandle<R>) -> Self {
+        Self(InnerJoinHandle::__Identified(id, Box::pin(inner)))
+    }
+
+    /// Returns the inner handle, without the [`Id`] of the task.
+    fn inner(&self) -> &InnerJoinHandle<R> {
+        match &self.0 {
+            InnerJoinHandle::__Identified(_, inner) => inner,
+            inner => inner,
+        }
+    }
+
+    /// Returns the [`Id`] of the task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle was constructed from an [`InnerJoinHandle`] by a custom
+    /// executor, which doesn't know the id. Use [`try_id`](JoinHandle::try_id) instead.
+    pub fn id(&self) -> Id {
+        self.try_id()
+            .expect("the handle wasn't returned by agnostik, so it has no task id")
+    }
+
+    /// Returns the [`Id`] of the task, or `None` if the handle was constructed from an
+    /// [`InnerJoinHandle`] by a custom executor.
+    pub fn try_id(&self) -> Option<Id> {
+        match self.0 {
+            InnerJoinHandle::__Identified(id, _) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the task was never spawned, because the global executor
+    /// was [shut down](crate::shutdown).
+    pub fn is_rejected(&self) -> bool {
+        matches!(self.inner(), InnerJoinHandle::Rejected)
+    }
+
+    /// Returns a future that awaits the result of the task, and returns an error
+    /// instead of panicking if the task failed.
+    pub fn try_join(self) -> TryJoin<R> {
+        TryJoin(self)
+    }
+
+    /// Drops the handle, but lets the task run to completion, even on executors
+    /// that cancel tasks whose handle is dropped.
+    #[cfg_attr(not(enable), allow(dead_code))]
+    pub(crate) fn detach(self) {
+        #[cfg(smol)]
+        {
+            let inner = match self.0 {
+                InnerJoinHandle::__Identified(_, inner) => *Pin::into_inner(inner),
+                inner => inner,
+            };
+            if let InnerJoinHandle::Smol(task) = inner {
+                task.detach();
+            }
+        }
+        #[cfg(not(smol))]
+        drop(self);
+    }
+}
+
 /// Inner join handle representation to hold variants
 /// of the executors
 #[pin_project::pin_project(project = JoinHandleProj)]
 pub enum InnerJoinHandle<R> {
     /// The `JoinHandle` which is used for the bastion executor.
     #[cfg(bastion)]
-    Bastion(#[pin] RecoverableHandle<R>),
+    Bastion(#[pin] RecoverableHandle<Result<R, JoinError>>),
     /// The `JoinHandle` which is used for the async_std runtime.
     #[cfg(async_std)]
     AsyncStd(#[pin] AsyncStdHandle<R>),
@@ -46,6 +112,19 @@ pub enum InnerJoinHandle<R> {
     /// The `JoinHandle` which is used for the smol runtime.
     #[cfg(smol)]
     Smol(#[pin] smol_crate::Task<R>),
+    /// The `JoinHandle` of a task that was spawned onto the global executor,
+    /// and can be cancelled when the executor is shut down, or of a task whose
+    /// result is delivered by agnostik itself, like a prioritized task.
+    ///
+    /// If the task panicked, awaiting this handle resumes the panic with its original
+    /// payload.
+    Global(Pin<Box<dyn Future<Output = Result<R, JoinError>> + Send>>),
+    /// The `JoinHandle` of a task that was rejected, because the global executor was shut down.
+    Rejected,
+
+    /// The handle of a task that was spawned by agnostik, together with the id of the task.
+    #[doc(hidden)]
+    __Identified(Id, Pin<Box<InnerJoinHandle<R>>>),
 
     /// Private element that can not be constructed.
     #[doc(hidden)]
@@ -73,9 +152,10 @@ where
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.project() {
             #[cfg(bastion)]
-            JoinHandleProj::Bastion(handle) => handle
-                .poll(cx)
-                .map(|val| val.expect("task failed to execute")),
+            JoinHandleProj::Bastion(handle) => handle.poll(cx).map(|val| {
+                val.expect("task failed to execute")
+                    .unwrap_or_else(|err| err.resume())
+            }),
             #[cfg(async_std)]
             JoinHandleProj::AsyncStd(handle) => handle.poll(cx),
             #[cfg(tokio)]
@@ -88,7 +168,67 @@ where
                 .map(|val| val.expect("task failed to execute")),
             #[cfg(smol)]
             JoinHandleProj::Smol(handle) => handle.poll(cx),
+            JoinHandleProj::Global(handle) => handle
+                .as_mut()
+                .poll(cx)
+                .map(|res| res.unwrap_or_else(|err| err.resume())),
+            JoinHandleProj::__Identified(_, inner) => inner.as_mut().poll(cx),
+            JoinHandleProj::Rejected => panic!("the executor was shut down"),
             JoinHandleProj::__Private(_, _) => unreachable!(),
         }
     }
 }
+
+impl<R> InnerJoinHandle<R> {
+    #[allow(unused_mut, unused_variables)]
+    fn poll_result(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<R, JoinError>> {
+        match self.project() {
+            #[cfg(bastion)]
+            JoinHandleProj::Bastion(handle) => handle
+                .poll(cx)
+                .map(|val| val.unwrap_or_else(|| Err(JoinError::cancelled()))),
+            #[cfg(async_std)]
+            JoinHandleProj::AsyncStd(handle) => handle.poll(cx).map(Ok),
+            #[cfg(tokio)]
+            JoinHandleProj::Tokio(handle) => handle.poll(cx).map(|res| {
+                res.map_err(|err| match err.try_into_panic() {
+                    Ok(payload) => JoinError::panic(payload),
+                    Err(_) => JoinError::cancelled(),
+                })
+            }),
+            #[cfg(tokio1)]
+            JoinHandleProj::Tokio1(handle) => handle.poll(cx).map(|res| {
+                res.map_err(|err| match err.try_into_panic() {
+                    Ok(payload) => JoinError::panic(payload),
+                    Err(_) => JoinError::cancelled(),
+                })
+            }),
+            #[cfg(smol)]
+            JoinHandleProj::Smol(handle) => handle.poll(cx).map(Ok),
+            JoinHandleProj::Global(handle) => handle.as_mut().poll(cx),
+            JoinHandleProj::__Identified(_, inner) => inner.as_mut().poll_result(cx),
+            JoinHandleProj::Rejected => Poll::Ready(Err(JoinError::cancelled())),
+            JoinHandleProj::__Private(_, _) => unreachable!(),
+        }
+    }
+}
+
+/// Future returned by [`JoinHandle::try_join`].
+///
+/// Resolves to a cancelled [`JoinError`] if the task was cancelled or
+/// [rejected](JoinHandle::is_rejected), and to a panic [`JoinError`] if the task panicked.
+/// The async-std and smol runtimes propagate panics of their tasks instead.
+#[pin_project::pin_project]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct TryJoin<R>(#[pin] JoinHandle<R>);
+
+impl<R> Future for TryJoin<R> {
+    type Output = Result<R, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().0.project().0.poll_result(cx)
+    }
+}
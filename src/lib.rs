@@ -12,6 +12,31 @@
 //! - Run futures and wait for them to finish
 //! - Spawn futures using the underlying executor
 //! - Spawn blocking tasks in threads that are able to execute blocking methods
+//! - Read from and write to the standard streams asynchronously
+//! - Resolve hostnames and open TCP connections without blocking the executor
+//! - Synchronize tasks using runtime independent locks, semaphores and more
+//! - Communicate between tasks using runtime independent channels
+//! - Spawn scoped tasks that borrow from the enclosing stack
+//! - Manage dynamic sets of spawned tasks and collect their results in completion order
+//! - Process streams in spawned tasks with a limit on how many run concurrently
+//! - Cancel trees of spawned tasks using hierarchical cancellation tokens
+//! - Shut down the global executor gracefully, with a deadline for its tasks
+//! - Give spawned tasks names, to tell them apart when debugging
+//! - Store request-scoped context in task-local values, which work the same on every executor
+//! - Propagate context like request ids and tracing spans into spawned tasks using spawn hooks
+//! - Trace the lifecycle of spawned tasks using `tracing`, with the `tracing` feature
+//! - Inspect runtime metrics, like task counts, the blocking pool and polls per worker
+//! - Find tasks that block the executor, using poll-duration histograms and slow-poll warnings
+//! - Dump the tasks that are alive to debug hangs, with the `task_dump` feature
+//! - Export the runtime metrics in the Prometheus text format, with the `prometheus` feature
+//! - Supervise tasks, restarting them with one-for-one, one-for-all or rest-for-one strategies
+//! - Observe when a task starts, completes or panics using lifecycle hooks
+//! - Report task panics uniformly on every backend, globally or per pool, and catch them using `spawn_catch_unwind`
+//! - Run latency-sensitive tasks before background work using task priorities
+//! - Isolate workloads on dedicated executor pools with their own threads
+//! - Pin the threads of executor pools and of the global tokio runtime to a set of CPUs on Linux
+//! - Retry operations with constant, exponential or jittered backoff
+//! - Sleep using runtime independent timers, whose clock can be paused in tests
 //!
 //! Every feature I just said, can be used with every executor provided by agnostik, or
 //! you can integrate your own executor with Agnostik.
@@ -152,10 +177,34 @@
 //! create a Runtime object using `Runtime::new()`.
 #![deny(rust_2018_idioms, clippy::pedantic, warnings, missing_docs)]
 
+#[cfg(target_os = "linux")]
+pub mod affinity;
+pub mod channel;
+#[cfg(feature = "task_dump")]
+pub mod debug;
 pub mod executor;
+pub mod io;
 pub mod join_handle;
+pub mod metrics;
+pub mod net;
+mod poll_timing;
+#[cfg(enable)]
+pub mod pool;
+mod retry;
+mod shutdown;
+pub mod supervisor;
+pub mod sync;
+pub mod task;
+pub mod time;
 #[cfg(feature = "attributes")]
 pub use agnostik_attributes::{bench, main, test};
+pub use metrics::{metrics, Metrics, WorkerMetrics};
+pub use poll_timing::{PollHistogram, PollTiming, SlowPoll};
+#[cfg(enable)]
+pub use pool::Pool;
+pub use retry::{retry, RetryPolicy};
+pub use shutdown::{is_shut_down, shutdown, ShutdownReport};
+pub use task::scope;
 
 use join_handle::JoinHandle;
 #[allow(unused)]
@@ -196,6 +245,69 @@ pub trait AgnostikExecutor {
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static;
+
+    /// Spawns an asynchronous task, which is stopped when the token is cancelled.
+    ///
+    /// The returned handle resolves to `None` if the task was stopped by the token,
+    /// in which case the future is dropped without being polled again.
+    #[track_caller]
+    fn spawn_with_token<F>(
+        &self,
+        token: &task::CancellationToken,
+        future: F,
+    ) -> JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let token = token.clone();
+        self.spawn(async move { token.run_until_cancelled(future).await })
+    }
+
+    /// Spawns an asynchronous task, which calls the hooks when it starts, completes or panics.
+    #[track_caller]
+    fn spawn_with_hooks<F>(&self, hooks: task::LifecycleHooks, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn(hooks.wrap(future))
+    }
+
+    /// Spawns an asynchronous task, whose handle resolves to the payload of the panic
+    /// if the task panics, instead of propagating the panic.
+    ///
+    /// Every backend reports the panic to the [panic handler](task::on_task_panic) of the
+    /// executor, or the global one, first.
+    #[track_caller]
+    fn spawn_catch_unwind<F>(
+        &self,
+        future: F,
+    ) -> JoinHandle<Result<F::Output, task::PanicPayload>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn(task::CatchUnwind::new(future))
+    }
+
+    /// Spawns an asynchronous task with the given priority.
+    ///
+    /// Prioritized tasks are run by worker tasks, which are spawned onto this executor,
+    /// and which always poll the ready task with the highest priority first. See
+    /// [`set_priority_workers`](task::set_priority_workers) for how many workers are used.
+    #[track_caller]
+    fn spawn_with_priority<F>(
+        &self,
+        priority: task::Priority,
+        future: F,
+    ) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        task::spawn_with_priority(self, priority, future)
+    }
 }
 
 /// This trait represents an executor that is capable of spawning futures onto the same thread.
@@ -311,22 +423,45 @@ impl Agnostik {
 
 /// `spawn` will use the global executor instance, which is determined by the cargo features,
 /// to spawn the given future.
+///
+/// If the global executor was [shut down](shutdown), the task isn't spawned,
+/// and a [rejected](JoinHandle::is_rejected) handle is returned.
+#[track_caller]
 pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
 where
     F: Future + Send + 'static,
     F::Output: Send + 'static,
 {
-    executor().spawn(future)
+    task::Builder::new().spawn(future)
+}
+
+/// `spawn_catch_unwind` will use the global executor instance, which is determined by the
+/// cargo features, to spawn the given future, and catches its panics.
+///
+/// The returned handle resolves to the payload of the panic if the task panicked.
+/// Like [`spawn`], it returns a [rejected](JoinHandle::is_rejected) handle if the
+/// global executor was [shut down](shutdown).
+#[track_caller]
+pub fn spawn_catch_unwind<F>(future: F) -> JoinHandle<Result<F::Output, task::PanicPayload>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    spawn(task::CatchUnwind::new(future))
 }
 
 /// `spawn_blocking` will use the global executor instance, which is determined by the cargo features,
 /// to spawn the given blocking task.
+///
+/// If the global executor was [shut down](shutdown), the task isn't spawned,
+/// and a [rejected](JoinHandle::is_rejected) handle is returned.
+#[track_caller]
 pub fn spawn_blocking<F, T>(task: F) -> JoinHandle<T>
 where
     F: FnOnce() -> T + Send + 'static,
     T: Send + 'static,
 {
-    executor().spawn_blocking(task)
+    task::Builder::new().spawn_blocking(task)
 }
 
 /// `block_on` will use the global executor instance, which is determined by the cargo features,
@@ -0,0 +1,253 @@
+//! Runtime metrics, collected by instrumenting the tasks spawned through agnostik.
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "prometheus")]
+pub use prometheus::encode_prometheus;
+
+use crate::poll_timing;
+pub use crate::poll_timing::PollHistogram;
+use once_cell::sync::Lazy;
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    thread,
+};
+
+static SPAWNED: AtomicU64 = AtomicU64::new(0);
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static PANICKED: AtomicU64 = AtomicU64::new(0);
+static CANCELLED: AtomicU64 = AtomicU64::new(0);
+
+static BLOCKING_THREADS: AtomicUsize = AtomicUsize::new(0);
+static BLOCKING_QUEUED: AtomicUsize = AtomicUsize::new(0);
+static BLOCKING_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// The threads that polled tasks, in the order in which they polled their first task.
+static WORKERS: Lazy<StdMutex<Vec<Arc<Worker>>>> = Lazy::new(|| StdMutex::new(Vec::new()));
+
+struct Worker {
+    name: Option<String>,
+    polls: AtomicU64,
+}
+
+/// Registers the current thread as a worker, and removes it when the thread exits.
+struct WorkerGuard(Arc<Worker>);
+
+impl WorkerGuard {
+    fn register() -> Self {
+        let worker = Arc::new(Worker {
+            name: thread::current().name().map(String::from),
+            polls: AtomicU64::new(0),
+        });
+        WORKERS.lock().unwrap().push(Arc::clone(&worker));
+        Self(worker)
+    }
+}
+
+impl Drop for WorkerGuard {
+    fn drop(&mut self) {
+        let mut workers = WORKERS.lock().unwrap();
+        workers.retain(|worker| !Arc::ptr_eq(worker, &self.0));
+    }
+}
+
+/// Counts the current thread as a blocking thread, until the thread exits.
+struct BlockingThreadGuard;
+
+impl BlockingThreadGuard {
+    fn register() -> Self {
+        BLOCKING_THREADS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for BlockingThreadGuard {
+    fn drop(&mut self) {
+        BLOCKING_THREADS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static WORKER: WorkerGuard = WorkerGuard::register();
+    static BLOCKING_THREAD: BlockingThreadGuard = BlockingThreadGuard::register();
+}
+
+/// How a task finished.
+#[derive(Clone, Copy)]
+pub(crate) enum Outcome {
+    Completed,
+    Panicked,
+    Cancelled,
+}
+
+pub(crate) fn spawned(blocking: bool) {
+    SPAWNED.fetch_add(1, Ordering::Relaxed);
+    if blocking {
+        BLOCKING_QUEUED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn finished(outcome: Outcome) {
+    let counter = match outcome {
+        Outcome::Completed => &COMPLETED,
+        Outcome::Panicked => &PANICKED,
+        Outcome::Cancelled => &CANCELLED,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a poll of a task on the current thread.
+pub(crate) fn polled() {
+    // the thread-local is gone while the thread exits
+    let _ = WORKER.try_with(|worker| worker.0.polls.fetch_add(1, Ordering::Relaxed));
+}
+
+/// Moves a blocking task out of the queue, once it starts running.
+pub(crate) fn blocking_started() {
+    let _ = BLOCKING_THREAD.try_with(|_| ());
+    BLOCKING_QUEUED.fetch_sub(1, Ordering::Relaxed);
+    BLOCKING_RUNNING.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn blocking_finished() {
+    BLOCKING_RUNNING.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Removes a blocking task from the queue, if it was dropped without running.
+pub(crate) fn blocking_dropped() {
+    BLOCKING_QUEUED.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Returns a snapshot of the metrics of all tasks spawned through agnostik.
+///
+/// The metrics are collected by agnostik itself, so they are available for every executor,
+/// but they don't include tasks that were spawned on the runtime directly. The counters
+/// are updated independently, so a snapshot taken while tasks are running may be
+/// slightly inconsistent.
+///
+/// ```ignore
+/// agnostik::block_on(agnostik::spawn(async {}));
+///
+/// let metrics = agnostik::metrics();
+/// assert!(metrics.completed() >= 1);
+/// ```
+pub fn metrics() -> Metrics {
+    let spawned = SPAWNED.load(Ordering::Relaxed);
+    let completed = COMPLETED.load(Ordering::Relaxed);
+    let panicked = PANICKED.load(Ordering::Relaxed);
+    let cancelled = CANCELLED.load(Ordering::Relaxed);
+
+    let workers = WORKERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|worker| WorkerMetrics {
+            name: worker.name.clone(),
+            polls: worker.polls.load(Ordering::Relaxed),
+        })
+        .collect();
+
+    Metrics {
+        spawned,
+        completed,
+        panicked,
+        cancelled,
+        alive: spawned.saturating_sub(completed + panicked + cancelled),
+        blocking_threads: BLOCKING_THREADS.load(Ordering::Relaxed),
+        blocking_running: BLOCKING_RUNNING.load(Ordering::Relaxed),
+        blocking_queue_depth: BLOCKING_QUEUED.load(Ordering::Relaxed),
+        workers,
+        poll_histograms: poll_timing::histograms(),
+    }
+}
+
+/// A snapshot of the runtime metrics, returned by [`metrics`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metrics {
+    spawned: u64,
+    completed: u64,
+    panicked: u64,
+    cancelled: u64,
+    alive: u64,
+    blocking_threads: usize,
+    blocking_running: usize,
+    blocking_queue_depth: usize,
+    workers: Vec<WorkerMetrics>,
+    poll_histograms: Vec<PollHistogram>,
+}
+
+impl Metrics {
+    /// Returns the number of tasks that were spawned, including blocking tasks.
+    pub fn spawned(&self) -> u64 {
+        self.spawned
+    }
+
+    /// Returns the number of tasks that ran to completion.
+    pub fn completed(&self) -> u64 {
+        self.completed
+    }
+
+    /// Returns the number of tasks that panicked.
+    pub fn panicked(&self) -> u64 {
+        self.panicked
+    }
+
+    /// Returns the number of tasks that were aborted, or dropped before they finished.
+    pub fn cancelled(&self) -> u64 {
+        self.cancelled
+    }
+
+    /// Returns the number of tasks that were spawned, but didn't finish yet.
+    pub fn alive(&self) -> u64 {
+        self.alive
+    }
+
+    /// Returns the number of threads that ran blocking tasks, and didn't exit yet.
+    pub fn blocking_threads(&self) -> usize {
+        self.blocking_threads
+    }
+
+    /// Returns the number of blocking tasks that are currently running.
+    pub fn blocking_running(&self) -> usize {
+        self.blocking_running
+    }
+
+    /// Returns the number of blocking tasks that are waiting for a thread.
+    pub fn blocking_queue_depth(&self) -> usize {
+        self.blocking_queue_depth
+    }
+
+    /// Returns the metrics of the threads that polled tasks, and didn't exit yet.
+    pub fn workers(&self) -> &[WorkerMetrics] {
+        &self.workers
+    }
+
+    /// Returns the histograms of the poll durations per task name, sorted by name.
+    ///
+    /// The histograms are empty unless the [timing of polls](crate::PollTiming) is enabled.
+    pub fn poll_histograms(&self) -> &[PollHistogram] {
+        &self.poll_histograms
+    }
+}
+
+/// Metrics of a single thread that polled tasks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkerMetrics {
+    name: Option<String>,
+    polls: u64,
+}
+
+impl WorkerMetrics {
+    /// Returns the name of the thread, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the number of times a task was polled on this thread.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+}
@@ -0,0 +1,147 @@
+use super::Metrics;
+use std::{
+    fmt::{self, Write},
+    time::Duration,
+};
+
+impl Metrics {
+    /// Renders the metrics in the Prometheus text format, and appends them to `out`.
+    ///
+    /// The poll-duration histograms are labeled with the task name, which is empty for
+    /// unnamed tasks, and the poll counts of the workers are labeled with the index and
+    /// the name of the thread.
+    pub fn encode_prometheus(&self, out: &mut String) {
+        // writing to a `String` never fails
+        let _ = self.encode(out);
+    }
+
+    fn encode(&self, out: &mut String) -> fmt::Result {
+        let counters = [
+            (
+                "agnostik_tasks_spawned_total",
+                "Number of tasks that were spawned.",
+                self.spawned,
+            ),
+            (
+                "agnostik_tasks_completed_total",
+                "Number of tasks that ran to completion.",
+                self.completed,
+            ),
+            (
+                "agnostik_tasks_panicked_total",
+                "Number of tasks that panicked.",
+                self.panicked,
+            ),
+            (
+                "agnostik_tasks_cancelled_total",
+                "Number of tasks that were cancelled.",
+                self.cancelled,
+            ),
+        ];
+        for (name, help, value) in &counters {
+            header(out, name, help, "counter")?;
+            writeln!(out, "{} {}", name, value)?;
+        }
+
+        let gauges = [
+            (
+                "agnostik_tasks_alive",
+                "Number of tasks that didn't finish yet.",
+                self.alive,
+            ),
+            (
+                "agnostik_blocking_threads",
+                "Number of threads that ran blocking tasks.",
+                self.blocking_threads as u64,
+            ),
+            (
+                "agnostik_blocking_tasks_running",
+                "Number of blocking tasks that are running.",
+                self.blocking_running as u64,
+            ),
+            (
+                "agnostik_blocking_queue_depth",
+                "Number of blocking tasks that wait for a thread.",
+                self.blocking_queue_depth as u64,
+            ),
+        ];
+        for (name, help, value) in &gauges {
+            header(out, name, help, "gauge")?;
+            writeln!(out, "{} {}", name, value)?;
+        }
+
+        let name = "agnostik_worker_polls_total";
+        header(
+            out,
+            name,
+            "Number of polls of tasks per worker thread.",
+            "counter",
+        )?;
+        for (index, worker) in self.workers.iter().enumerate() {
+            write!(out, "{}{{worker=\"{}\"", name, index)?;
+            if let Some(thread) = worker.name() {
+                write!(out, ",thread=\"{}\"", escape(thread))?;
+            }
+            writeln!(out, "}} {}", worker.polls())?;
+        }
+
+        let name = "agnostik_poll_duration_seconds";
+        header(
+            out,
+            name,
+            "Durations of the polls of tasks per task name.",
+            "histogram",
+        )?;
+        for histogram in &self.poll_histograms {
+            let task = escape(histogram.name().unwrap_or_default());
+            let mut count = 0;
+            for (bound, polls) in histogram.buckets() {
+                count += polls;
+                let le = if bound == Duration::MAX {
+                    "+Inf".to_string()
+                } else {
+                    bound.as_secs_f64().to_string()
+                };
+                writeln!(
+                    out,
+                    "{}_bucket{{task=\"{}\",le=\"{}\"}} {}",
+                    name, task, le, count
+                )?;
+            }
+            writeln!(
+                out,
+                "{}_sum{{task=\"{}\"}} {}",
+                name,
+                task,
+                histogram.total().as_secs_f64()
+            )?;
+            writeln!(out, "{}_count{{task=\"{}\"}} {}", name, task, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders a snapshot of the [metrics](super::metrics) in the Prometheus text format,
+/// and appends them to `out`.
+///
+/// ```ignore
+/// let mut out = String::new();
+/// agnostik::metrics::encode_prometheus(&mut out);
+/// assert!(out.contains("agnostik_tasks_spawned_total"));
+/// ```
+pub fn encode_prometheus(out: &mut String) {
+    super::metrics().encode_prometheus(out);
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) -> fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} {}", name, kind)
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,558 @@
+//! Asynchronous hostname resolution and TCP sockets.
+//!
+//! Resolving a hostname using [`std::net::ToSocketAddrs`] blocks the current thread,
+//! which must never happen on a thread of the executor. This module provides
+//! the [`AsyncToSocketAddrs`] trait and the [`lookup_host`] function, which resolve
+//! hostnames without blocking the executor.
+//!
+//! The resolver of the runtime is used if it provides one (tokio, async-std and smol).
+//! Otherwise, the lookup is executed using [`spawn_blocking`](crate::spawn_blocking).
+//!
+//! ```ignore
+//! agnostik::block_on(async {
+//!     for addr in agnostik::net::lookup_host("localhost:8080").await? {
+//!         println!("localhost resolves to {}", addr);
+//!     }
+//!     Ok::<_, std::io::Error>(())
+//! });
+//! ```
+//!
+//! [`TcpStream::connect`] and [`TcpListener::bind`] accept every [`AsyncToSocketAddrs`]
+//! value, and try the resolved addresses one after another. The streams implement the
+//! `futures-io` traits, like the [standard streams](crate::io). The sockets of the runtime
+//! are used if it provides them, while bastion runs the blocking sockets of the standard
+//! library on its blocking threads.
+//!
+//! ```ignore
+//! use agnostik::net::TcpStream;
+//! use futures_util::io::AsyncWriteExt;
+//!
+//! agnostik::block_on(async {
+//!     let mut stream = TcpStream::connect("localhost:8080").await?;
+//!     stream.write_all(b"ping").await
+//! });
+//! ```
+
+#[cfg(any(tokio, tokio1))]
+use crate::io::compat::{tokio, Compat};
+use futures_io::{AsyncRead, AsyncWrite};
+use std::{
+    fmt,
+    future::Future,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    pin::Pin,
+    task::{Context, Poll},
+    vec,
+};
+
+/// Performs a DNS resolution, without blocking the executor.
+///
+/// The returned iterator may not actually yield any values,
+/// depending on the outcome of the lookup.
+///
+/// # Errors
+///
+/// Returns an error if the value is not a valid socket address, or if the lookup failed.
+pub async fn lookup_host<T>(host: T) -> io::Result<SocketAddrs>
+where
+    T: AsyncToSocketAddrs,
+{
+    host.to_socket_addrs().await
+}
+
+/// Converts or resolves a value to one or more [`SocketAddr`] values, without
+/// blocking the executor.
+///
+/// This is the asynchronous counterpart of [`std::net::ToSocketAddrs`], and is implemented
+/// for the same types. Types that don't require a lookup resolve immediately.
+pub trait AsyncToSocketAddrs {
+    /// Converts this value into a future that resolves to the socket addresses.
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture;
+}
+
+/// Future returned by [`AsyncToSocketAddrs::to_socket_addrs`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ToSocketAddrsFuture(State);
+
+enum State {
+    Ready(Option<io::Result<Vec<SocketAddr>>>),
+    Resolving(Pin<Box<dyn Future<Output = io::Result<Vec<SocketAddr>>> + Send>>),
+}
+
+impl ToSocketAddrsFuture {
+    fn ready(addrs: Vec<SocketAddr>) -> Self {
+        ToSocketAddrsFuture(State::Ready(Some(Ok(addrs))))
+    }
+
+    fn err(err: io::Error) -> Self {
+        ToSocketAddrsFuture(State::Ready(Some(Err(err))))
+    }
+
+    /// Resolves a `host:port` string, without a lookup if the host is an IP address.
+    fn resolve(addr: String) -> Self {
+        if let Ok(addr) = addr.parse::<SocketAddr>() {
+            return Self::ready(vec![addr]);
+        }
+        ToSocketAddrsFuture(State::Resolving(Box::pin(resolve(addr))))
+    }
+
+    /// Resolves a host and a port, without a lookup if the host is an IP address.
+    fn resolve_host(host: &str, port: u16) -> Self {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Self::ready(vec![SocketAddr::new(ip, port)]);
+        }
+        Self::resolve(format!("{}:{}", host, port))
+    }
+}
+
+impl Future for ToSocketAddrsFuture {
+    type Output = io::Result<SocketAddrs>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = match &mut self.0 {
+            State::Ready(res) => res
+                .take()
+                .expect("`ToSocketAddrsFuture` polled after completion"),
+            State::Resolving(fut) => futures_core::ready!(fut.as_mut().poll(cx)),
+        };
+        self.0 = State::Ready(None);
+        Poll::Ready(res.map(|addrs| SocketAddrs(addrs.into_iter())))
+    }
+}
+
+impl fmt::Debug for ToSocketAddrsFuture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("ToSocketAddrsFuture { .. }")
+    }
+}
+
+/// An iterator over the resolved socket addresses.
+///
+/// Returned by [`lookup_host`] and [`ToSocketAddrsFuture`].
+#[derive(Debug)]
+pub struct SocketAddrs(vec::IntoIter<SocketAddr>);
+
+impl Iterator for SocketAddrs {
+    type Item = SocketAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SocketAddrs {}
+
+/// Resolves a `host:port` string using the resolver of the runtime.
+#[cfg(any(tokio, tokio1))]
+async fn resolve(addr: String) -> io::Result<Vec<SocketAddr>> {
+    Ok(tokio::net::lookup_host(addr).await?.collect())
+}
+
+/// Resolves a `host:port` string using the resolver of the runtime.
+#[cfg(async_std)]
+async fn resolve(addr: String) -> io::Result<Vec<SocketAddr>> {
+    use async_std_crate::net::ToSocketAddrs;
+
+    Ok(ToSocketAddrs::to_socket_addrs(&addr).await?.collect())
+}
+
+/// Resolves a `host:port` string using the resolver of the runtime.
+#[cfg(smol)]
+async fn resolve(addr: String) -> io::Result<Vec<SocketAddr>> {
+    smol_crate::net::resolve(addr).await
+}
+
+/// Resolves a `host:port` string on a blocking thread.
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+#[cfg_attr(not(enable), allow(unreachable_code))]
+async fn resolve(addr: String) -> io::Result<Vec<SocketAddr>> {
+    use std::net::ToSocketAddrs;
+
+    crate::spawn_blocking(move || Ok(ToSocketAddrs::to_socket_addrs(&addr)?.collect())).await
+}
+
+impl AsyncToSocketAddrs for SocketAddr {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        ToSocketAddrsFuture::ready(vec![*self])
+    }
+}
+
+impl AsyncToSocketAddrs for SocketAddrV4 {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        SocketAddr::V4(*self).to_socket_addrs()
+    }
+}
+
+impl AsyncToSocketAddrs for SocketAddrV6 {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        SocketAddr::V6(*self).to_socket_addrs()
+    }
+}
+
+impl AsyncToSocketAddrs for (IpAddr, u16) {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        SocketAddr::new(self.0, self.1).to_socket_addrs()
+    }
+}
+
+impl AsyncToSocketAddrs for (Ipv4Addr, u16) {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        SocketAddrV4::new(self.0, self.1).to_socket_addrs()
+    }
+}
+
+impl AsyncToSocketAddrs for (Ipv6Addr, u16) {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        SocketAddrV6::new(self.0, self.1, 0, 0).to_socket_addrs()
+    }
+}
+
+impl AsyncToSocketAddrs for (&str, u16) {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        ToSocketAddrsFuture::resolve_host(self.0, self.1)
+    }
+}
+
+impl AsyncToSocketAddrs for (String, u16) {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        ToSocketAddrsFuture::resolve_host(&self.0, self.1)
+    }
+}
+
+impl AsyncToSocketAddrs for str {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        // validate the format upfront, so the error doesn't depend on the resolver
+        match self.rsplit_once(':').map(|(_, port)| port.parse::<u16>()) {
+            Some(Ok(_)) => ToSocketAddrsFuture::resolve(self.to_owned()),
+            Some(Err(_)) => ToSocketAddrsFuture::err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid port value",
+            )),
+            None => ToSocketAddrsFuture::err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid socket address",
+            )),
+        }
+    }
+}
+
+impl AsyncToSocketAddrs for String {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        self.as_str().to_socket_addrs()
+    }
+}
+
+impl AsyncToSocketAddrs for [SocketAddr] {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        ToSocketAddrsFuture::ready(self.to_vec())
+    }
+}
+
+impl<T: AsyncToSocketAddrs + ?Sized> AsyncToSocketAddrs for &T {
+    fn to_socket_addrs(&self) -> ToSocketAddrsFuture {
+        (**self).to_socket_addrs()
+    }
+}
+
+#[cfg(any(tokio, tokio1))]
+type StreamInner = Compat<tokio::net::TcpStream>;
+#[cfg(async_std)]
+type StreamInner = async_std_crate::net::TcpStream;
+#[cfg(smol)]
+type StreamInner = smol_crate::net::TcpStream;
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+type StreamInner = BlockingStream;
+
+#[cfg(any(tokio, tokio1))]
+type ListenerInner = tokio::net::TcpListener;
+#[cfg(async_std)]
+type ListenerInner = async_std_crate::net::TcpListener;
+#[cfg(smol)]
+type ListenerInner = smol_crate::net::TcpListener;
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+type ListenerInner = std::net::TcpListener;
+
+/// Calls the closure with every resolved address, until it succeeds, and returns the
+/// last error otherwise.
+async fn each_addr<A, F, Fut, T>(addr: A, mut f: F) -> io::Result<T>
+where
+    A: AsyncToSocketAddrs,
+    F: FnMut(SocketAddr) -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut last_err = None;
+    for addr in lookup_host(addr).await? {
+        match f(addr).await {
+            Ok(output) => return Ok(output),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any address",
+        )
+    }))
+}
+
+/// A TCP stream between a local and a remote socket.
+///
+/// The stream implements [`AsyncRead`] and [`AsyncWrite`] from the `futures-io` crate.
+pub struct TcpStream {
+    inner: StreamInner,
+}
+
+impl TcpStream {
+    /// Opens a TCP connection to a remote host.
+    ///
+    /// If the address resolves to multiple socket addresses, a connection is attempted to
+    /// each of them, until one succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address couldn't be resolved, or if no connection could
+    /// be opened to any of the resolved addresses. The error of the last attempt is returned.
+    pub async fn connect(addr: impl AsyncToSocketAddrs) -> io::Result<TcpStream> {
+        let inner = each_addr(addr, connect).await?;
+        Ok(TcpStream { inner })
+    }
+
+    /// Returns the local address of the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address couldn't be read from the socket.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        #[cfg(any(tokio, tokio1))]
+        return self.inner.get_ref().local_addr();
+        #[cfg(any(async_std, smol))]
+        return self.inner.local_addr();
+        #[cfg(not(any(tokio, tokio1, async_std, smol)))]
+        return self.inner.socket.local_addr();
+    }
+
+    /// Returns the address of the remote host, that the stream is connected to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address couldn't be read from the socket.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        #[cfg(any(tokio, tokio1))]
+        return self.inner.get_ref().peer_addr();
+        #[cfg(any(async_std, smol))]
+        return self.inner.peer_addr();
+        #[cfg(not(any(tokio, tokio1, async_std, smol)))]
+        return self.inner.socket.peer_addr();
+    }
+}
+
+/// Connects to a single address, using the sockets of the runtime.
+#[cfg(any(tokio, tokio1))]
+async fn connect(addr: SocketAddr) -> io::Result<StreamInner> {
+    Ok(Compat::new(tokio::net::TcpStream::connect(addr).await?))
+}
+
+/// Connects to a single address, using the sockets of the runtime.
+#[cfg(async_std)]
+async fn connect(addr: SocketAddr) -> io::Result<StreamInner> {
+    async_std_crate::net::TcpStream::connect(addr).await
+}
+
+/// Connects to a single address, using the sockets of the runtime.
+#[cfg(smol)]
+async fn connect(addr: SocketAddr) -> io::Result<StreamInner> {
+    smol_crate::net::TcpStream::connect(addr).await
+}
+
+/// Connects to a single address on a blocking thread.
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+#[cfg_attr(not(enable), allow(unreachable_code))]
+async fn connect(addr: SocketAddr) -> io::Result<StreamInner> {
+    let socket = crate::spawn_blocking(move || std::net::TcpStream::connect(addr)).await?;
+    BlockingStream::new(socket)
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl fmt::Debug for TcpStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpStream")
+            .field("local_addr", &self.local_addr().ok())
+            .field("peer_addr", &self.peer_addr().ok())
+            .finish()
+    }
+}
+
+/// A blocking TCP stream, whose reads and writes run on blocking threads.
+///
+/// [`Blocking`](crate::io::blocking::Blocking) only drives one direction, so the reads and
+/// writes use separate handles to the same socket.
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+struct BlockingStream {
+    socket: std::net::TcpStream,
+    reader: crate::io::blocking::Blocking<std::net::TcpStream>,
+    writer: crate::io::blocking::Blocking<std::net::TcpStream>,
+}
+
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+impl BlockingStream {
+    fn new(socket: std::net::TcpStream) -> io::Result<Self> {
+        use crate::io::blocking::Blocking;
+
+        Ok(Self {
+            reader: Blocking::new(socket.try_clone()?),
+            writer: Blocking::new(socket.try_clone()?),
+            socket,
+        })
+    }
+}
+
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+impl AsyncRead for BlockingStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+impl AsyncWrite for BlockingStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures_core::ready!(Pin::new(&mut self.writer).poll_close(cx))?;
+        Poll::Ready(self.socket.shutdown(std::net::Shutdown::Write))
+    }
+}
+
+/// A TCP socket, which listens for connections.
+pub struct TcpListener {
+    inner: ListenerInner,
+}
+
+impl TcpListener {
+    /// Creates a listener, which is bound to the address.
+    ///
+    /// If the address resolves to multiple socket addresses, the listener is bound to
+    /// the first one that succeeds. Binding to port 0 assigns a free port, which is
+    /// returned by [`local_addr`](Self::local_addr).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address couldn't be resolved, or if the listener couldn't
+    /// be bound to any of the resolved addresses. The error of the last attempt is returned.
+    pub async fn bind(addr: impl AsyncToSocketAddrs) -> io::Result<TcpListener> {
+        let inner = each_addr(addr, bind).await?;
+        Ok(TcpListener { inner })
+    }
+
+    /// Accepts a new connection, and returns the stream and the address of the remote host.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection couldn't be accepted.
+    #[cfg_attr(not(enable), allow(unreachable_code))]
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        #[cfg(any(tokio, tokio1))]
+        let (inner, addr) = {
+            let (stream, addr) = self.inner.accept().await?;
+            (Compat::new(stream), addr)
+        };
+        #[cfg(any(async_std, smol))]
+        let (inner, addr) = self.inner.accept().await?;
+        #[cfg(not(any(tokio, tokio1, async_std, smol)))]
+        let (inner, addr) = {
+            let listener = self.inner.try_clone()?;
+            let (socket, addr) = crate::spawn_blocking(move || listener.accept()).await?;
+            (BlockingStream::new(socket)?, addr)
+        };
+        Ok((TcpStream { inner }, addr))
+    }
+
+    /// Returns the local address, that the listener is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address couldn't be read from the socket.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+}
+
+/// Binds a listener to a single address, using the sockets of the runtime.
+#[cfg(any(tokio, tokio1))]
+async fn bind(addr: SocketAddr) -> io::Result<ListenerInner> {
+    tokio::net::TcpListener::bind(addr).await
+}
+
+/// Binds a listener to a single address, using the sockets of the runtime.
+#[cfg(async_std)]
+async fn bind(addr: SocketAddr) -> io::Result<ListenerInner> {
+    async_std_crate::net::TcpListener::bind(addr).await
+}
+
+/// Binds a listener to a single address, using the sockets of the runtime.
+#[cfg(smol)]
+async fn bind(addr: SocketAddr) -> io::Result<ListenerInner> {
+    smol_crate::net::TcpListener::bind(addr).await
+}
+
+/// Binds a listener to a single address.
+///
+/// Binding doesn't block, so it's done on the current thread.
+#[cfg(not(any(tokio, tokio1, async_std, smol)))]
+async fn bind(addr: SocketAddr) -> io::Result<ListenerInner> {
+    std::net::TcpListener::bind(addr)
+}
+
+impl fmt::Debug for TcpListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpListener")
+            .field("local_addr", &self.local_addr().ok())
+            .finish()
+    }
+}
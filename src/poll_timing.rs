@@ -0,0 +1,252 @@
+//! Timing of the polls of spawned tasks, to find tasks that block the executor.
+
+use crate::task::Task;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex as StdMutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// The upper bounds of the buckets of a [`PollHistogram`].
+const BOUNDS: [Duration; 7] = [
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::MAX,
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CONFIG: Lazy<RwLock<Option<Arc<PollTiming>>>> = Lazy::new(|| RwLock::new(None));
+static HISTOGRAMS: Lazy<StdMutex<HashMap<Option<String>, PollHistogram>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+type Callback = dyn Fn(&SlowPoll) + Send + Sync;
+
+/// Configuration of the timing of polls, which is disabled by default.
+///
+/// Once [enabled](PollTiming::enable), every poll of a task spawned through agnostik is
+/// timed and recorded in a histogram per task name, which are part of the
+/// [`metrics`](crate::metrics). Polls that take longer than the threshold are reported
+/// to the callback, or as a `tracing` warning if no callback is set and the `tracing`
+/// feature is enabled.
+///
+/// ```ignore
+/// use agnostik::PollTiming;
+/// use std::time::Duration;
+///
+/// PollTiming::new()
+///     .slow_poll_threshold(Duration::from_millis(10))
+///     .on_slow_poll(|poll| eprintln!("{}", poll))
+///     .enable();
+/// ```
+#[derive(Clone, Default)]
+pub struct PollTiming {
+    threshold: Option<Duration>,
+    callback: Option<Arc<Callback>>,
+}
+
+impl PollTiming {
+    /// Creates a configuration that records the histograms, but doesn't report slow polls.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports polls that take longer than the threshold.
+    pub fn slow_poll_threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Calls the closure for every slow poll, instead of emitting a `tracing` warning.
+    ///
+    /// The closure is called on the thread that polled the task, right after the poll.
+    pub fn on_slow_poll(mut self, f: impl Fn(&SlowPoll) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Arc::new(f));
+        self
+    }
+
+    /// Enables the timing of polls, replacing the previous configuration.
+    pub fn enable(self) {
+        *CONFIG.write().unwrap() = Some(Arc::new(self));
+        ENABLED.store(true, Ordering::SeqCst);
+    }
+
+    /// Disables the timing of polls. The recorded histograms are kept.
+    pub fn disable() {
+        ENABLED.store(false, Ordering::SeqCst);
+        *CONFIG.write().unwrap() = None;
+    }
+}
+
+impl fmt::Debug for PollTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PollTiming")
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A poll that took longer than the [threshold](PollTiming::slow_poll_threshold).
+#[derive(Debug, Clone)]
+pub struct SlowPoll {
+    task: Task,
+    duration: Duration,
+}
+
+impl SlowPoll {
+    /// Returns the task that was polled.
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    /// Returns how long the poll took.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl fmt::Display for SlowPoll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.task.name() {
+            Some(name) => write!(f, "task '{}'", name)?,
+            None => write!(f, "task {}", self.task.id())?,
+        }
+        write!(
+            f,
+            " spawned at {} was polled for {:?}",
+            self.task.location(),
+            self.duration
+        )
+    }
+}
+
+/// Histogram of the poll durations of all tasks with the same name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PollHistogram {
+    name: Option<String>,
+    buckets: [u64; BOUNDS.len()],
+    total: Duration,
+    max: Duration,
+    slow: u64,
+}
+
+impl PollHistogram {
+    fn new(name: Option<String>) -> Self {
+        Self {
+            name,
+            buckets: [0; BOUNDS.len()],
+            total: Duration::default(),
+            max: Duration::default(),
+            slow: 0,
+        }
+    }
+
+    /// Returns the name of the tasks, or `None` for unnamed tasks.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the number of polls.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Returns the total time spent polling.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the duration of the longest poll.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Returns the number of polls that took longer than the threshold.
+    pub fn slow_polls(&self) -> u64 {
+        self.slow
+    }
+
+    /// Returns the buckets of the histogram, as the exclusive upper bound of the
+    /// poll durations in the bucket and the number of polls in it.
+    ///
+    /// The bounds grow by a factor of ten, from 10µs up to one second, and the last
+    /// bucket is unbounded, with an upper bound of [`Duration::MAX`].
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        BOUNDS.iter().copied().zip(self.buckets.iter().copied())
+    }
+
+    fn record(&mut self, duration: Duration, slow: bool) {
+        let bucket = BOUNDS
+            .iter()
+            .position(|bound| duration < *bound)
+            .unwrap_or(BOUNDS.len() - 1);
+        self.buckets[bucket] += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+        if slow {
+            self.slow += 1;
+        }
+    }
+}
+
+/// Times the poll of a task, if the timing of polls is enabled.
+pub(crate) fn time<T>(task: &Task, poll: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return poll();
+    }
+
+    let start = Instant::now();
+    let output = poll();
+    let duration = start.elapsed();
+    if let Some(config) = CONFIG.read().unwrap().clone() {
+        record(&config, task, duration);
+    }
+    output
+}
+
+fn record(config: &PollTiming, task: &Task, duration: Duration) {
+    let slow = config
+        .threshold
+        .map_or(false, |threshold| duration > threshold);
+
+    HISTOGRAMS
+        .lock()
+        .unwrap()
+        .entry(task.name().map(String::from))
+        .or_insert_with_key(|name| PollHistogram::new(name.clone()))
+        .record(duration, slow);
+
+    if slow {
+        let poll = SlowPoll {
+            task: task.clone(),
+            duration,
+        };
+        match &config.callback {
+            Some(callback) => callback(&poll),
+            #[cfg(feature = "tracing")]
+            None => tracing::warn!(task.id = task.id().as_u64(), "{}", poll),
+            #[cfg(not(feature = "tracing"))]
+            None => {}
+        }
+    }
+}
+
+/// Returns the histograms of all task names, sorted by name.
+pub(crate) fn histograms() -> Vec<PollHistogram> {
+    let mut histograms = HISTOGRAMS
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    histograms.sort_by(|a, b| a.name.cmp(&b.name));
+    histograms
+}
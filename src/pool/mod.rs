@@ -0,0 +1,385 @@
+//! Dedicated executor pools, which isolate workloads from each other.
+//!
+//! A [`Pool`] is an independent [`AgnostikExecutor`] with its own threads, which can be
+//! used alongside the global [`executor`](crate::executor). The tokio backends create a
+//! separate runtime, the smol backend runs a separate `Executor` on its own threads, and
+//! the async-std and bastion backends, whose runtimes are global, use a native pool of
+//! agnostik.
+//!
+//! ```ignore
+//! use agnostik::{prelude::*, Pool};
+//!
+//! let cpu = Pool::builder().threads(4).name("cpu").build()?;
+//! let checksum = agnostik::block_on(cpu.spawn(async { compute_checksum() }));
+//! ```
+//!
+//! On Linux, the threads of a pool can be pinned to a set of CPUs using
+//! [`PoolBuilder::pin_threads`].
+
+#[cfg(any(async_std, bastion))]
+mod native;
+
+#[cfg(any(tokio, tokio1, smol))]
+use crate::{join_handle::InnerJoinHandle, task::TaskFuture};
+use crate::{
+    join_handle::JoinHandle,
+    task::{self, PanicHandler, PanicPayload, PriorityScheduler, Task},
+    AgnostikExecutor,
+};
+use std::{fmt, future::Future, io, num::NonZeroUsize, sync::Arc, thread};
+
+/// Runs on every thread of a pool, before it starts to run tasks.
+type OnStart = Arc<dyn Fn() + Send + Sync>;
+
+/// An independent executor with its own threads, which is shut down when it's dropped.
+///
+/// Tasks are only spawned onto the pool using its [`AgnostikExecutor`] methods.
+/// With the tokio backends, [`spawn`](crate::spawn) inside of a task of the pool spawns
+/// onto the pool as well, while the other backends spawn onto the global executor.
+///
+/// When the pool is dropped, its threads stop, and the tasks that didn't finish yet are
+/// dropped. Blocking tasks run on the blocking threads of the backend, which are shared
+/// with the global executor. Prioritized tasks are scheduled by the pool itself, so they
+/// only compete with the other prioritized tasks of the pool.
+///
+/// See the [module documentation](self) for more details.
+pub struct Pool {
+    name: String,
+    threads: usize,
+    inner: Inner,
+    panic_handler: Option<PanicHandler>,
+    /// Runs the tasks spawned using `spawn_with_priority`, separately from other executors.
+    scheduler: Arc<PriorityScheduler>,
+    /// The CPUs that blocking tasks are pinned to, while they run.
+    #[cfg(target_os = "linux")]
+    blocking_cpus: Option<Arc<[usize]>>,
+}
+
+enum Inner {
+    #[cfg(tokio)]
+    Tokio(Option<tokio_crate::runtime::Runtime>),
+    #[cfg(tokio1)]
+    Tokio1(Option<tokio1_crate::runtime::Runtime>),
+    #[cfg(smol)]
+    Smol(SmolPool),
+    #[cfg(any(async_std, bastion))]
+    Native(native::NativePool),
+}
+
+impl Pool {
+    /// Returns a builder, which configures a new pool.
+    pub fn builder() -> PoolBuilder {
+        PoolBuilder {
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            name: "agnostik-pool".into(),
+            panic_handler: None,
+            #[cfg(target_os = "linux")]
+            cpus: None,
+            #[cfg(target_os = "linux")]
+            pin_blocking: false,
+        }
+    }
+
+    /// Returns the name of the threads of the pool.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of threads of the pool, which run its asynchronous tasks.
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+}
+
+impl AgnostikExecutor for Pool {
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let task = Task::with_panic_handler(None, self.panic_handler.clone());
+        #[cfg(any(tokio, tokio1, smol))]
+        let id = task.id();
+        match &self.inner {
+            #[cfg(tokio)]
+            Inner::Tokio(runtime) => {
+                let handle = runtime_of(runtime.as_ref()).spawn(TaskFuture::new(task, future));
+                JoinHandle::new(id, InnerJoinHandle::Tokio(handle))
+            }
+            #[cfg(tokio1)]
+            Inner::Tokio1(runtime) => {
+                let handle = runtime_of(runtime.as_ref()).spawn(TaskFuture::new(task, future));
+                JoinHandle::new(id, InnerJoinHandle::Tokio1(handle))
+            }
+            #[cfg(smol)]
+            Inner::Smol(pool) => {
+                let handle = pool.executor.spawn(TaskFuture::new(task, future));
+                JoinHandle::new(id, InnerJoinHandle::Smol(handle))
+            }
+            #[cfg(any(async_std, bastion))]
+            Inner::Native(pool) => pool.spawn(task, future),
+        }
+    }
+
+    #[track_caller]
+    fn spawn_blocking<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = Task::with_panic_handler(None, self.panic_handler.clone());
+        #[cfg(any(tokio, tokio1, smol))]
+        let id = task.id();
+        #[cfg(target_os = "linux")]
+        let f = {
+            let cpus = self.blocking_cpus.clone();
+            move || match cpus {
+                Some(cpus) => crate::affinity::pinned(&cpus, f),
+                None => f(),
+            }
+        };
+        match &self.inner {
+            #[cfg(tokio)]
+            Inner::Tokio(runtime) => {
+                let handle =
+                    runtime_of(runtime.as_ref()).spawn_blocking(TaskFuture::blocking(task, f));
+                JoinHandle::new(id, InnerJoinHandle::Tokio(handle))
+            }
+            #[cfg(tokio1)]
+            Inner::Tokio1(runtime) => {
+                let handle =
+                    runtime_of(runtime.as_ref()).spawn_blocking(TaskFuture::blocking(task, f));
+                JoinHandle::new(id, InnerJoinHandle::Tokio1(handle))
+            }
+            #[cfg(smol)]
+            Inner::Smol(pool) => {
+                let f = smol_crate::unblock(TaskFuture::blocking(task, f));
+                JoinHandle::new(id, InnerJoinHandle::Smol(pool.executor.spawn(f)))
+            }
+            #[cfg(any(async_std, bastion))]
+            Inner::Native(_) => crate::EXECUTOR.spawn_blocking_task(task, f),
+        }
+    }
+
+    fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        match &self.inner {
+            #[cfg(tokio)]
+            Inner::Tokio(runtime) => runtime_of(runtime.as_ref()).block_on(future),
+            #[cfg(tokio1)]
+            Inner::Tokio1(runtime) => runtime_of(runtime.as_ref()).block_on(future),
+            #[cfg(smol)]
+            Inner::Smol(pool) => smol_crate::block_on(pool.executor.spawn(future)),
+            #[cfg(any(async_std, bastion))]
+            Inner::Native(_) => crate::EXECUTOR.block_on(self.spawn(future)),
+        }
+    }
+
+    #[track_caller]
+    fn spawn_with_priority<F>(&self, priority: task::Priority, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let task = Task::with_panic_handler(None, self.panic_handler.clone());
+        task::spawn_with_priority_on(&self.scheduler, self, task, priority, future)
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.scheduler.close();
+
+        // the pool may be dropped inside of a task, where tokio doesn't allow to block
+        #[cfg(tokio)]
+        let Inner::Tokio(runtime) = &mut self.inner;
+        #[cfg(tokio1)]
+        let Inner::Tokio1(runtime) = &mut self.inner;
+        #[cfg(any(tokio, tokio1))]
+        if let Some(runtime) = runtime.take() {
+            runtime.shutdown_background();
+        }
+
+        // the threads of a smol pool stop once the sender is dropped
+        #[cfg(any(async_std, bastion))]
+        let Inner::Native(pool) = &self.inner;
+        #[cfg(any(async_std, bastion))]
+        pool.shutdown();
+    }
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("name", &self.name)
+            .field("threads", &self.threads)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the runtime of a pool, which is only taken when the pool is dropped.
+#[cfg(any(tokio, tokio1))]
+fn runtime_of<R>(runtime: Option<&R>) -> &R {
+    runtime.expect("the pool was shut down")
+}
+
+/// A smol `Executor`, which is run by its own threads.
+#[cfg(smol)]
+struct SmolPool {
+    executor: Arc<smol_crate::Executor<'static>>,
+    /// Stops the threads when it's dropped.
+    _stop: smol_crate::channel::Sender<()>,
+}
+
+/// Configures a new [`Pool`], returned by [`Pool::builder`].
+#[derive(Clone, Debug)]
+pub struct PoolBuilder {
+    threads: usize,
+    name: String,
+    panic_handler: Option<PanicHandler>,
+    #[cfg(target_os = "linux")]
+    cpus: Option<Vec<usize>>,
+    #[cfg(target_os = "linux")]
+    pin_blocking: bool,
+}
+
+impl PoolBuilder {
+    /// Sets the number of threads, which run the asynchronous tasks of the pool.
+    ///
+    /// Defaults to the number of CPUs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "a pool needs at least one thread");
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the name of the threads of the pool.
+    ///
+    /// Defaults to `agnostik-pool`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the handler, which is called whenever a task of the pool panics, instead of
+    /// the [global handler](crate::task::on_task_panic).
+    pub fn on_task_panic(
+        mut self,
+        handler: impl Fn(&Task, &PanicPayload) + Send + Sync + 'static,
+    ) -> Self {
+        self.panic_handler = Some(PanicHandler::new(handler));
+        self
+    }
+
+    /// Pins every thread of the pool to the CPUs with the given ids.
+    ///
+    /// Blocking tasks are only pinned if [`pin_blocking_tasks`](Self::pin_blocking_tasks)
+    /// is enabled, and otherwise keep the CPUs of the thread that built the pool.
+    #[cfg(target_os = "linux")]
+    pub fn pin_threads(mut self, cpus: impl IntoIterator<Item = usize>) -> Self {
+        self.cpus = Some(cpus.into_iter().collect());
+        self
+    }
+
+    /// Sets whether blocking tasks of the pool are pinned to the CPUs of
+    /// [`pin_threads`](Self::pin_threads) while they run.
+    ///
+    /// Defaults to `false`.
+    #[cfg(target_os = "linux")]
+    pub fn pin_blocking_tasks(mut self, pin: bool) -> Self {
+        self.pin_blocking = pin;
+        self
+    }
+
+    /// Creates the pool, and starts its threads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the threads couldn't be started, or if the pool should be pinned
+    /// to CPUs that don't exist or aren't available to the current thread.
+    pub fn build(self) -> io::Result<Pool> {
+        let on_start: OnStart = Arc::new(|| {});
+        #[cfg(target_os = "linux")]
+        let (on_start, blocking_cpus) = match &self.cpus {
+            Some(cpus) => {
+                crate::affinity::check(cpus)?;
+                let cpus: Arc<[usize]> = cpus.as_slice().into();
+                // tokio runs `on_thread_start` on its blocking threads as well
+                let blocking_cpus = if self.pin_blocking {
+                    cpus.clone()
+                } else {
+                    crate::affinity::current_thread_affinity()?.into()
+                };
+                let on_start: OnStart = Arc::new(move || {
+                    // the CPUs were checked above
+                    let _ = crate::affinity::pin_current_thread(&cpus);
+                });
+                (on_start, Some(blocking_cpus))
+            }
+            None => (on_start, None),
+        };
+
+        #[cfg(tokio)]
+        let inner = Inner::Tokio(Some(
+            tokio_crate::runtime::Builder::new_multi_thread()
+                .worker_threads(self.threads)
+                .thread_name(&self.name)
+                .on_thread_start(move || on_start())
+                .enable_all()
+                .build()?,
+        ));
+        #[cfg(tokio1)]
+        let inner = Inner::Tokio1(Some(
+            tokio1_crate::runtime::Builder::new_multi_thread()
+                .worker_threads(self.threads)
+                .thread_name(&self.name)
+                .on_thread_start(move || on_start())
+                .enable_all()
+                .build()?,
+        ));
+        #[cfg(smol)]
+        let inner = {
+            let executor = Arc::new(smol_crate::Executor::new());
+            let (stop, stopped) = smol_crate::channel::bounded::<()>(1);
+            for _ in 0..self.threads {
+                let (executor, stopped) = (executor.clone(), stopped.clone());
+                let on_start = on_start.clone();
+                thread::Builder::new()
+                    .name(self.name.clone())
+                    .spawn(move || {
+                        on_start();
+                        // fails once the sender is dropped, which stops the thread
+                        let _ = smol_crate::block_on(executor.run(stopped.recv()));
+                    })?;
+            }
+            Inner::Smol(SmolPool {
+                executor,
+                _stop: stop,
+            })
+        };
+        #[cfg(any(async_std, bastion))]
+        let inner = Inner::Native(native::NativePool::new(
+            self.threads,
+            &self.name,
+            &on_start,
+        )?);
+
+        Ok(Pool {
+            name: self.name,
+            threads: self.threads,
+            inner,
+            panic_handler: self.panic_handler,
+            scheduler: PriorityScheduler::new(),
+            #[cfg(target_os = "linux")]
+            blocking_cpus,
+        })
+    }
+}
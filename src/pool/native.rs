@@ -0,0 +1,177 @@
+//! The native pool of agnostik, for backends whose runtime is global.
+
+use super::OnStart;
+use crate::{
+    join_handle::JoinHandle,
+    task::{remote, Task},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex as StdMutex, Weak,
+    },
+    task::{Context, Wake, Waker},
+    thread,
+};
+
+/// A pool of threads, which poll the tasks in their run queue.
+pub(super) struct NativePool {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: StdMutex<State>,
+    /// Notified whenever a task was queued, or the pool was shut down.
+    available: Condvar,
+}
+
+struct State {
+    queue: VecDeque<Arc<Job>>,
+    /// All tasks that didn't finish yet, so they can be dropped when the pool is shut down.
+    jobs: HashMap<u64, Weak<Job>>,
+    next_id: u64,
+    shut_down: bool,
+}
+
+struct Job {
+    id: u64,
+    pool: Weak<Shared>,
+    future: StdMutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>,
+    /// Set while the task is in the run queue, so it's only queued once.
+    queued: AtomicBool,
+}
+
+impl NativePool {
+    pub(super) fn new(threads: usize, name: &str, on_start: &OnStart) -> io::Result<Self> {
+        let shared = Arc::new(Shared {
+            state: StdMutex::new(State {
+                queue: VecDeque::new(),
+                jobs: HashMap::new(),
+                next_id: 0,
+                shut_down: false,
+            }),
+            available: Condvar::new(),
+        });
+        let pool = Self { shared };
+        for _ in 0..threads {
+            let (shared, on_start) = (pool.shared.clone(), on_start.clone());
+            thread::Builder::new().name(name.into()).spawn(move || {
+                on_start();
+                shared.run();
+            })?;
+        }
+        Ok(pool)
+    }
+
+    pub(super) fn spawn<F>(&self, task: Task, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (future, handle) = remote(task, future);
+        let job = {
+            let mut state = self.shared.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            let job = Arc::new(Job {
+                id,
+                pool: Arc::downgrade(&self.shared),
+                future: StdMutex::new(Some(Box::pin(future))),
+                queued: AtomicBool::new(false),
+            });
+            state.jobs.insert(id, Arc::downgrade(&job));
+            job
+        };
+        job.schedule();
+        handle
+    }
+
+    /// Stops the threads, and drops the tasks that didn't finish yet.
+    pub(super) fn shutdown(&self) {
+        let jobs = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.shut_down = true;
+            state.queue.clear();
+            state.jobs.drain().map(|(_, job)| job).collect::<Vec<_>>()
+        };
+        self.shared.available.notify_all();
+
+        // tasks that are polled right now are dropped by their thread afterwards
+        for job in jobs.iter().filter_map(Weak::upgrade) {
+            if let Ok(mut future) = job.future.try_lock() {
+                future.take();
+            }
+        }
+    }
+}
+
+impl Shared {
+    /// Polls the queued tasks, until the pool is shut down.
+    fn run(&self) {
+        loop {
+            let job = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    if state.shut_down {
+                        return;
+                    }
+                    if let Some(job) = state.queue.pop_front() {
+                        break job;
+                    }
+                    state = self.available.wait(state).unwrap();
+                }
+            };
+            job.run(self);
+        }
+    }
+}
+
+impl Job {
+    fn schedule(self: &Arc<Self>) {
+        if self.queued.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Some(pool) = self.pool.upgrade() {
+            let mut state = pool.state.lock().unwrap();
+            if !state.shut_down {
+                state.queue.push_back(Arc::clone(self));
+                drop(state);
+                pool.available.notify_one();
+            }
+        }
+    }
+
+    /// Polls the task once.
+    fn run(self: Arc<Self>, pool: &Shared) {
+        self.queued.store(false, Ordering::SeqCst);
+        let waker = Waker::from(Arc::clone(&self));
+        let mut slot = self.future.lock().unwrap();
+        let finished = slot.as_mut().map_or(false, |future| {
+            future
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+        });
+
+        let mut state = pool.state.lock().unwrap();
+        if finished || state.shut_down {
+            state.jobs.remove(&self.id);
+            drop(state);
+            slot.take();
+        }
+    }
+}
+
+impl Wake for Job {
+    fn wake(self: Arc<Self>) {
+        self.schedule();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.schedule();
+    }
+}
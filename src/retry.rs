@@ -0,0 +1,143 @@
+use std::{
+    collections::hash_map::RandomState,
+    convert::TryFrom,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// Decides how often, and with which delays, [`retry`] attempts an operation.
+///
+/// By default, the operation is attempted at most 3 times.
+///
+/// ```ignore
+/// use agnostik::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::exponential(Duration::from_millis(100), Duration::from_secs(5))
+///     .jitter()
+///     .max_attempts(10)
+///     .deadline(Duration::from_secs(30));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    initial: Duration,
+    max: Duration,
+    jitter: bool,
+    max_attempts: usize,
+    deadline: Option<Duration>,
+}
+
+impl RetryPolicy {
+    /// Waits the same delay before every retry.
+    pub fn constant(delay: Duration) -> Self {
+        Self::exponential(delay, delay)
+    }
+
+    /// Doubles the delay for every retry, starting with `initial`, up to `max`.
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            jitter: false,
+            max_attempts: 3,
+            deadline: None,
+        }
+    }
+
+    /// Randomizes every delay between half of it and the full delay, so operations that
+    /// failed at the same time don't retry at the same time.
+    pub fn jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+
+    /// Attempts the operation at most `max_attempts` times, including the first attempt.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is zero.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "at least one attempt is required");
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Gives up, instead of retrying, if the retry would start more than `deadline`
+    /// after the first attempt.
+    ///
+    /// Attempts that are running when the deadline passes aren't cancelled.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Returns the delay before the retry after the given number of failed attempts.
+    fn delay(&self, failures: usize) -> Duration {
+        let factor = u32::try_from(failures - 1)
+            .ok()
+            .and_then(|failures| 1_u32.checked_shl(failures))
+            .unwrap_or(u32::MAX);
+        let delay = self
+            .initial
+            .checked_mul(factor)
+            .map_or(self.max, |delay| delay.min(self.max));
+        if self.jitter {
+            jitter(delay)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Returns a random duration between half of the delay and the full delay.
+fn jitter(delay: Duration) -> Duration {
+    // every `RandomState` is seeded differently, which is random enough for jitter
+    let random = RandomState::new().build_hasher().finish();
+    let half = delay / 2;
+    let range = u64::try_from(delay.saturating_sub(half).as_nanos()).unwrap_or(u64::MAX);
+    half + Duration::from_nanos(random % range.saturating_add(1))
+}
+
+/// Calls the operation until it succeeds, waiting between the attempts according to the policy.
+///
+/// The delays use the [runtime independent timers](crate::time), so `retry` works with
+/// every executor, and can be tested by [pausing](crate::time::pause) the clock.
+///
+/// ```ignore
+/// use agnostik::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::exponential(Duration::from_millis(100), Duration::from_secs(5));
+/// let response = agnostik::retry(policy, || fetch("https://example.com")).await?;
+/// ```
+///
+/// # Errors
+///
+/// Returns the error of the last attempt, if the operation failed as often as the policy
+/// allows, or if the next retry would start after the deadline of the policy.
+pub async fn retry<F, Fut, T, E>(policy: RetryPolicy, mut operation: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let started = crate::time::now();
+    let deadline = policy.deadline.map(|deadline| started + deadline);
+    let mut failures = 0;
+    loop {
+        let err = match operation().await {
+            Ok(output) => return Ok(output),
+            Err(err) => err,
+        };
+        failures += 1;
+        if failures >= policy.max_attempts {
+            return Err(err);
+        }
+
+        let retry_at = crate::time::now() + policy.delay(failures);
+        if deadline.map_or(false, |deadline| retry_at > deadline) {
+            return Err(err);
+        }
+        crate::time::sleep_until(retry_at).await;
+    }
+}
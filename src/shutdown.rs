@@ -0,0 +1,349 @@
+//! Graceful shutdown of the global executor.
+
+use crate::{
+    join_handle::{InnerJoinHandle, JoinHandle},
+    task::{abortable, spawn_blocking_task, spawn_task, trace, AbortHandle, JoinError, Task},
+};
+use once_cell::sync::Lazy;
+use std::{
+    future::Future,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Condvar, Mutex as StdMutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// The number of shards of the abort registry, which spreads concurrent spawns over
+/// multiple locks.
+const SHARDS: usize = 16;
+
+/// Set in a counter of the tracker once the executor was shut down. The other bits
+/// count the tasks, so a task is either counted or rejected.
+const SHUT_DOWN: usize = 1 << (usize::BITS - 1);
+
+static TRACKER: Lazy<Tracker> = Lazy::new(|| Tracker {
+    tasks: AtomicUsize::new(0),
+    blocking: AtomicUsize::new(0),
+    cancelled: AtomicBool::new(false),
+    cancelled_blocking: AtomicUsize::new(0),
+    registry: Default::default(),
+    lock: StdMutex::new(()),
+    idle: Condvar::new(),
+});
+
+/// Keeps track of the tasks that were spawned onto the global executor.
+///
+/// Spawning and completing a task only touches atomic counters and a shard of the
+/// registry. The lock is only taken once the executor is shut down.
+struct Tracker {
+    tasks: AtomicUsize,
+    blocking: AtomicUsize,
+    /// Set once the deadline passed, so blocking tasks that didn't start yet are skipped.
+    cancelled: AtomicBool,
+    cancelled_blocking: AtomicUsize,
+    /// The abort handles of the tasks that didn't finish yet.
+    registry: [StdMutex<Slab>; SHARDS],
+    /// Locked by `shutdown`, while it waits for the tasks to finish.
+    lock: StdMutex<()>,
+    /// Notified when the last task or blocking task finished after the shutdown.
+    idle: Condvar,
+}
+
+impl Tracker {
+    /// Counts a new task, unless the executor was shut down.
+    fn start(counter: &AtomicUsize) -> bool {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count & SHUT_DOWN == 0).then_some(count + 1)
+            })
+            .is_ok()
+    }
+
+    fn finish(&self, counter: &AtomicUsize) {
+        // wake up `shutdown` if it waits for the last task
+        if counter.fetch_sub(1, Ordering::SeqCst) == SHUT_DOWN | 1 {
+            let _lock = self.lock.lock().unwrap();
+            self.idle.notify_all();
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.tasks.load(Ordering::SeqCst) & !SHUT_DOWN == 0
+            && self.blocking.load(Ordering::SeqCst) & !SHUT_DOWN == 0
+    }
+}
+
+/// The abort handles of a shard, whose slots are reused once a task finished.
+#[derive(Default)]
+struct Slab {
+    entries: Vec<Option<AbortHandle>>,
+    free: Vec<usize>,
+}
+
+impl Slab {
+    fn insert(&mut self, handle: AbortHandle) -> usize {
+        if let Some(index) = self.free.pop() {
+            self.entries[index] = Some(handle);
+            index
+        } else {
+            self.entries.push(Some(handle));
+            self.entries.len() - 1
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        // the entries are drained when the deadline of the shutdown passes
+        if let Some(entry) = self.entries.get_mut(index) {
+            if entry.take().is_some() {
+                self.free.push(index);
+            }
+        }
+    }
+
+    fn drain(&mut self) -> Vec<AbortHandle> {
+        self.free.clear();
+        mem::take(&mut self.entries).into_iter().flatten().collect()
+    }
+}
+
+/// Removes a task from the tracker when it's dropped.
+enum TaskGuard {
+    Task { shard: usize, index: usize },
+    Blocking,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        match *self {
+            Self::Task { shard, index } => {
+                TRACKER.registry[shard].lock().unwrap().remove(index);
+                TRACKER.finish(&TRACKER.tasks);
+            }
+            Self::Blocking => TRACKER.finish(&TRACKER.blocking),
+        }
+    }
+}
+
+/// A tracked task, which removes itself from the tracker when it completes or is dropped.
+#[pin_project::pin_project]
+struct Tracked<F> {
+    #[pin]
+    future: F,
+    guard: TaskGuard,
+}
+
+impl<F: Future> Future for Tracked<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        self.project().future.poll(cx)
+    }
+}
+
+/// Spawns the future onto the global executor, unless it was shut down.
+#[cfg_attr(not(enable), allow(unreachable_code))]
+pub(crate) fn spawn<F>(task: Task, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    if !Tracker::start(&TRACKER.tasks) {
+        return JoinHandle::new(task.id(), InnerJoinHandle::Rejected);
+    }
+
+    let (future, handle) = abortable(future);
+    #[allow(clippy::cast_possible_truncation)]
+    let shard = (task.id().as_u64() % SHARDS as u64) as usize;
+    let index = {
+        let mut slab = TRACKER.registry[shard].lock().unwrap();
+        // the deadline of the shutdown may have passed before the task was registered
+        if TRACKER.cancelled.load(Ordering::SeqCst) {
+            handle.abort();
+        }
+        slab.insert(handle)
+    };
+    let guard = TaskGuard::Task { shard, index };
+
+    let id = task.id();
+    let handle = spawn_task(task.clone(), Tracked { future, guard });
+    JoinHandle::new(id, InnerJoinHandle::Global(Box::pin(async move {
+        let res = handle.try_join().await.and_then(|res| res);
+        res.map_err(|err| err.with_task(task))
+    })))
+}
+
+/// Spawns the blocking task onto the global executor, unless it was shut down.
+#[cfg_attr(not(enable), allow(unreachable_code))]
+pub(crate) fn spawn_blocking<F, T>(task: Task, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    if !Tracker::start(&TRACKER.blocking) {
+        return JoinHandle::new(task.id(), InnerJoinHandle::Rejected);
+    }
+    let guard = TaskGuard::Blocking;
+
+    let id = task.id();
+    let handle = spawn_blocking_task(task.clone(), move || {
+        let _guard = guard;
+        if TRACKER.cancelled.load(Ordering::SeqCst) {
+            TRACKER.cancelled_blocking.fetch_add(1, Ordering::SeqCst);
+            trace::aborted();
+            return Err(JoinError::cancelled());
+        }
+        panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+            trace::panicked(&payload);
+            JoinError::panic(payload)
+        })
+    });
+    JoinHandle::new(id, InnerJoinHandle::Global(Box::pin(async move {
+        let res = handle.try_join().await.and_then(|res| res);
+        res.map_err(|err| err.with_task(task))
+    })))
+}
+
+/// Shuts down the global executor, waiting at most `timeout` for its tasks to finish.
+///
+/// Once this function was called, [`spawn`](crate::spawn) and
+/// [`spawn_blocking`](crate::spawn_blocking) no longer accept new tasks, and
+/// return a [rejected](JoinHandle::is_rejected) handle instead. The executor
+/// can't be restarted afterwards.
+///
+/// Tasks that are still running when the deadline passes are cancelled, and awaiting
+/// them returns a cancelled [`JoinError`] from [`try_join`](JoinHandle::try_join).
+/// Blocking tasks can't be interrupted, so this function waits until all running
+/// blocking tasks finished, but skips those that didn't start before the deadline.
+/// If the tokio runtime is used, it's shut down as well, unless it's currently
+/// used by [`block_on`](crate::block_on).
+///
+/// Only tasks spawned using the global functions are tracked, not those spawned using
+/// the methods of [`executor()`](crate::executor).
+///
+/// This function blocks the current thread, so it must not be called from within
+/// an asynchronous task.
+///
+/// ```ignore
+/// use std::time::Duration;
+///
+/// let handle = agnostik::spawn(futures::future::pending::<()>());
+///
+/// let report = agnostik::shutdown(Duration::from_secs(1));
+/// assert_eq!(report.cancelled(), 1);
+/// assert!(agnostik::spawn(async {}).is_rejected());
+/// ```
+pub fn shutdown(timeout: Duration) -> ShutdownReport {
+    let deadline = Instant::now() + timeout;
+
+    let mut lock = TRACKER.lock.lock().unwrap();
+    let in_flight = TRACKER.tasks.fetch_or(SHUT_DOWN, Ordering::SeqCst) & !SHUT_DOWN;
+    let blocking = TRACKER.blocking.fetch_or(SHUT_DOWN, Ordering::SeqCst) & !SHUT_DOWN;
+
+    // wait for the in-flight tasks to finish
+    while !TRACKER.is_idle() {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        lock = TRACKER.idle.wait_timeout(lock, deadline - now).unwrap().0;
+    }
+
+    // cancel the remaining tasks
+    TRACKER.cancelled.store(true, Ordering::SeqCst);
+    let aborted = TRACKER
+        .registry
+        .iter()
+        .flat_map(|slab| slab.lock().unwrap().drain())
+        .collect::<Vec<_>>();
+    aborted.iter().for_each(AbortHandle::abort);
+
+    // join the blocking tasks
+    while TRACKER.blocking.load(Ordering::SeqCst) & !SHUT_DOWN > 0 {
+        lock = TRACKER.idle.wait(lock).unwrap();
+    }
+    drop(lock);
+    let cancelled_blocking = TRACKER.cancelled_blocking.load(Ordering::SeqCst);
+
+    shutdown_runtime(deadline.saturating_duration_since(Instant::now()));
+
+    ShutdownReport {
+        completed: in_flight - aborted.len(),
+        cancelled: aborted.len(),
+        completed_blocking: blocking - cancelled_blocking,
+        cancelled_blocking,
+    }
+}
+
+/// Returns `true` once [`shutdown`] was called, after which the global executor rejects
+/// new tasks.
+pub fn is_shut_down() -> bool {
+    TRACKER.tasks.load(Ordering::SeqCst) & SHUT_DOWN != 0
+}
+
+#[cfg(tokio)]
+fn shutdown_runtime(timeout: Duration) {
+    use std::any::Any;
+
+    let executor = crate::executor() as &dyn Any;
+    match executor.downcast_ref::<crate::executor::TokioExecutor>() {
+        Some(executor) => executor.shutdown_runtime(timeout),
+        None => unreachable!(),
+    }
+}
+
+#[cfg(tokio1)]
+fn shutdown_runtime(timeout: Duration) {
+    use std::any::Any;
+
+    let executor = crate::executor() as &dyn Any;
+    match executor.downcast_ref::<crate::executor::Tokio1Executor>() {
+        Some(executor) => executor.shutdown_runtime(timeout),
+        None => unreachable!(),
+    }
+}
+
+#[cfg(not(any(tokio, tokio1)))]
+fn shutdown_runtime(_: Duration) {}
+
+/// Report of a [`shutdown`], describing which tasks were cancelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShutdownReport {
+    completed: usize,
+    cancelled: usize,
+    completed_blocking: usize,
+    cancelled_blocking: usize,
+}
+
+impl ShutdownReport {
+    /// Returns the number of tasks that finished before the deadline.
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// Returns the number of tasks that were cancelled, because they didn't
+    /// finish before the deadline.
+    pub fn cancelled(&self) -> usize {
+        self.cancelled
+    }
+
+    /// Returns the number of blocking tasks that were joined.
+    pub fn completed_blocking(&self) -> usize {
+        self.completed_blocking
+    }
+
+    /// Returns the number of blocking tasks that were skipped, because they
+    /// didn't start before the deadline.
+    pub fn cancelled_blocking(&self) -> usize {
+        self.cancelled_blocking
+    }
+
+    /// Returns `true` if no task was cancelled.
+    pub fn is_graceful(&self) -> bool {
+        self.cancelled == 0 && self.cancelled_blocking == 0
+    }
+}
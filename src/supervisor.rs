@@ -0,0 +1,364 @@
+//! Supervision of tasks, which restarts them when they fail.
+//!
+//! A [`Supervisor`] runs a list of children, which are created by future factories, and
+//! restarts them according to its [`Strategy`] when they panic. If the children are
+//! restarted too often within a period, the supervisor gives up, stops all children and
+//! returns a [`SupervisorError`], so the failure can be escalated to its caller.
+//!
+//! ```ignore
+//! use agnostik::supervisor::{Backoff, Strategy, Supervisor};
+//! use std::time::Duration;
+//!
+//! let supervisor = Supervisor::new(Strategy::OneForOne)
+//!     .restart_intensity(5, Duration::from_secs(10))
+//!     .backoff(Backoff::exponential(Duration::from_millis(10), Duration::from_secs(1)))
+//!     .child("listener", || async { run_listener().await })
+//!     .child("cleanup", || async { run_cleanup().await });
+//!
+//! agnostik::block_on(supervisor.run()).unwrap();
+//! ```
+
+use crate::{
+    task::{abortable, AbortHandle, JoinError, TaskGroup},
+    AgnostikExecutor,
+};
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+type Factory = Box<dyn FnMut() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// Decides which children are restarted, when a child fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Only the failed child is restarted.
+    OneForOne,
+    /// All other children are stopped, and all children are restarted.
+    OneForAll,
+    /// The children that were started after the failed child are stopped, and the
+    /// failed child and those children are restarted.
+    RestForOne,
+}
+
+/// Decides whether a child is restarted, when it finishes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Restart {
+    /// The child is always restarted.
+    Permanent,
+    /// The child is only restarted if it panicked.
+    Transient,
+    /// The child is never restarted.
+    Temporary,
+}
+
+/// The delay before children are restarted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    /// Restarts the children immediately.
+    pub fn none() -> Self {
+        Self::fixed(Duration::from_secs(0))
+    }
+
+    /// Waits the same delay before every restart.
+    pub fn fixed(delay: Duration) -> Self {
+        Self {
+            initial: delay,
+            max: delay,
+        }
+    }
+
+    /// Doubles the delay for every restart within the period of the
+    /// [restart intensity](Supervisor::restart_intensity), up to `max`.
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Self { initial, max }
+    }
+
+    /// Returns the delay after the given number of recent restarts.
+    fn delay(self, restarts: usize) -> Duration {
+        let factor = u32::try_from(restarts)
+            .ok()
+            .and_then(|restarts| 1_u32.checked_shl(restarts))
+            .unwrap_or(u32::MAX);
+        self.initial
+            .checked_mul(factor)
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
+
+/// Supervises a list of children, and restarts them when they fail.
+///
+/// See the [module documentation](self) for more details.
+pub struct Supervisor {
+    strategy: Strategy,
+    max_restarts: usize,
+    period: Duration,
+    backoff: Backoff,
+    children: Vec<Child>,
+}
+
+struct Child {
+    name: String,
+    restart: Restart,
+    factory: Factory,
+    /// Stops the child, while it's running.
+    abort: Option<AbortHandle>,
+    /// Incremented whenever the child is started, to tell apart its runs.
+    generation: u64,
+}
+
+/// Reported by a child when it exits.
+type Exit = (usize, u64, Result<(), JoinError>);
+
+impl Supervisor {
+    /// Creates a supervisor without children.
+    ///
+    /// By default, the children may be restarted 3 times within 5 seconds,
+    /// and they are restarted immediately.
+    pub fn new(strategy: Strategy) -> Self {
+        Self {
+            strategy,
+            max_restarts: 3,
+            period: Duration::from_secs(5),
+            backoff: Backoff::none(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Gives up if more than `max_restarts` restarts happen within `period`.
+    pub fn restart_intensity(mut self, max_restarts: usize, period: Duration) -> Self {
+        self.max_restarts = max_restarts;
+        self.period = period;
+        self
+    }
+
+    /// Sets the delay before children are restarted.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Adds a [permanent](Restart::Permanent) child.
+    ///
+    /// The factory is called whenever the child is started.
+    pub fn child<F, Fut>(self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.child_with(name, Restart::Permanent, factory)
+    }
+
+    /// Adds a child, which is restarted according to `restart`.
+    ///
+    /// The factory is called whenever the child is started.
+    pub fn child_with<F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        restart: Restart,
+        mut factory: F,
+    ) -> Self
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.children.push(Child {
+            name: name.into(),
+            restart,
+            factory: Box::new(move || Box::pin(factory())),
+            abort: None,
+            generation: 0,
+        });
+        self
+    }
+
+    /// Runs the children on the global executor, until all of them finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the children were restarted too often.
+    pub async fn run(self) -> Result<(), SupervisorError> {
+        self.run_on(crate::executor()).await
+    }
+
+    /// Runs the children on the given executor, until all of them finished.
+    ///
+    /// The children are stopped if the returned future is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the children were restarted too often.
+    pub async fn run_on<E: AgnostikExecutor>(
+        mut self,
+        executor: &E,
+    ) -> Result<(), SupervisorError> {
+        let mut group = TaskGroup::new();
+        let mut exits = VecDeque::new();
+        let mut restarts = VecDeque::new();
+
+        for index in 0..self.children.len() {
+            self.start(executor, &mut group, index);
+        }
+
+        while self.children.iter().any(|child| child.abort.is_some()) {
+            let (failed, generation, result) = match exits.pop_front() {
+                Some(exit) => exit,
+                None => match group.join_next().await {
+                    Some(Ok(exit)) => exit,
+                    // the executor dropped a child, so it's shutting down
+                    Some(Err(_)) | None => break,
+                },
+            };
+
+            let child = &mut self.children[failed];
+            if child.generation != generation || child.abort.is_none() {
+                continue;
+            }
+            child.abort = None;
+
+            let restart = match child.restart {
+                Restart::Permanent => true,
+                Restart::Transient => result.is_err(),
+                Restart::Temporary => false,
+            };
+            if !restart {
+                continue;
+            }
+
+            let now = Instant::now();
+            while restarts
+                .front()
+                .map_or(false, |restart| now.duration_since(*restart) >= self.period)
+            {
+                restarts.pop_front();
+            }
+            if restarts.len() >= self.max_restarts {
+                let stopped = (0..self.children.len()).collect::<Vec<_>>();
+                self.stop(&mut group, &mut exits, &stopped).await;
+                return Err(SupervisorError {
+                    child: self.children[failed].name.clone(),
+                    restarts: restarts.len(),
+                    period: self.period,
+                });
+            }
+            restarts.push_back(now);
+
+            let affected = match self.strategy {
+                Strategy::OneForOne => vec![failed],
+                Strategy::OneForAll => (0..self.children.len()).collect(),
+                Strategy::RestForOne => (failed..self.children.len()).collect(),
+            };
+            let stopped = self.stop(&mut group, &mut exits, &affected).await;
+
+            crate::time::sleep(self.backoff.delay(restarts.len() - 1)).await;
+
+            // temporary children are stopped, but not restarted
+            for index in affected {
+                let child = &self.children[index];
+                if index == failed
+                    || (stopped.contains(&index) && child.restart != Restart::Temporary)
+                {
+                    self.start(executor, &mut group, index);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn start<E: AgnostikExecutor>(
+        &mut self,
+        executor: &E,
+        group: &mut TaskGroup<Exit>,
+        index: usize,
+    ) {
+        let child = &mut self.children[index];
+        child.generation += 1;
+        let generation = child.generation;
+
+        let (future, abort) = abortable((child.factory)());
+        child.abort = Some(abort);
+        group.spawn_on(executor, async move { (index, generation, future.await) });
+    }
+
+    /// Stops the running children with the given indices, in reverse order, and
+    /// returns the indices of the children that were running.
+    ///
+    /// Exits of other children are kept in `exits`.
+    async fn stop(
+        &mut self,
+        group: &mut TaskGroup<Exit>,
+        exits: &mut VecDeque<Exit>,
+        indices: &[usize],
+    ) -> Vec<usize> {
+        let mut stopping = Vec::new();
+        for &index in indices.iter().rev() {
+            if let Some(abort) = self.children[index].abort.take() {
+                abort.abort();
+                stopping.push(index);
+            }
+        }
+
+        let mut pending = stopping.clone();
+        while !pending.is_empty() {
+            match group.join_next().await {
+                Some(Ok(exit)) => {
+                    let (index, generation, _) = exit;
+                    match pending.iter().position(|pending| *pending == index) {
+                        Some(position) if self.children[index].generation == generation => {
+                            pending.swap_remove(position);
+                        }
+                        _ => exits.push_back(exit),
+                    }
+                }
+                Some(Err(_)) | None => break,
+            }
+        }
+        stopping
+    }
+}
+
+impl fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("strategy", &self.strategy)
+            .field("children", &self.children.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by a [`Supervisor`], if its children were restarted too often.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupervisorError {
+    child: String,
+    restarts: usize,
+    period: Duration,
+}
+
+impl SupervisorError {
+    /// Returns the name of the child whose failure exceeded the restart intensity.
+    pub fn child(&self) -> &str {
+        &self.child
+    }
+}
+
+impl fmt::Display for SupervisorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "child '{}' failed after {} restarts within {:?}",
+            self.child, self.restarts, self.period
+        )
+    }
+}
+
+impl Error for SupervisorError {}
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Mutex as StdMutex,
+    task::{Context, Poll, Waker},
+};
+
+/// A barrier that enables multiple tasks to synchronize the beginning of some computation.
+///
+/// All tasks that call [`wait`](Barrier::wait) are suspended, until `n` tasks are waiting.
+/// Then all of them are woken up at once, and the barrier can be reused.
+pub struct Barrier {
+    n: usize,
+    state: StdMutex<State>,
+}
+
+struct State {
+    arrived: usize,
+    generation: u64,
+    next_id: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+impl Barrier {
+    /// Creates a new barrier that releases the waiting tasks when `n` tasks called
+    /// [`wait`](Barrier::wait).
+    ///
+    /// A barrier for zero tasks behaves like a barrier for one task.
+    pub fn new(n: usize) -> Self {
+        Self {
+            n: n.max(1),
+            state: StdMutex::new(State {
+                arrived: 0,
+                generation: 0,
+                next_id: 0,
+                wakers: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Waits until all tasks have reached this point.
+    ///
+    /// Exactly one of the released tasks receives a [`BarrierWaitResult`] for which
+    /// [`is_leader`](BarrierWaitResult::is_leader) returns `true`.
+    ///
+    /// If the returned future is dropped before the barrier was released,
+    /// the task is no longer counted as waiting.
+    pub fn wait(&self) -> BarrierWait<'_> {
+        BarrierWait {
+            barrier: self,
+            waiting: None,
+        }
+    }
+}
+
+impl fmt::Debug for Barrier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("Barrier")
+            .field("n", &self.n)
+            .field("arrived", &state.arrived)
+            .finish()
+    }
+}
+
+/// Future returned by [`Barrier::wait`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct BarrierWait<'a> {
+    barrier: &'a Barrier,
+    /// The generation and the waker id, if this future is waiting.
+    waiting: Option<(u64, u64)>,
+}
+
+impl Future for BarrierWait<'_> {
+    type Output = BarrierWaitResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.barrier.state.lock().unwrap();
+
+        if let Some((generation, id)) = self.waiting {
+            if state.generation != generation {
+                drop(state);
+                self.waiting = None;
+                return Poll::Ready(BarrierWaitResult(false));
+            }
+
+            state.wakers.insert(id, cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        state.arrived += 1;
+        if state.arrived == self.barrier.n {
+            state.arrived = 0;
+            state.generation += 1;
+            let wakers = state.wakers.drain().map(|(_, waker)| waker).collect::<Vec<_>>();
+            drop(state);
+
+            wakers.into_iter().for_each(Waker::wake);
+            return Poll::Ready(BarrierWaitResult(true));
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        state.wakers.insert(id, cx.waker().clone());
+        self.waiting = Some((state.generation, id));
+        Poll::Pending
+    }
+}
+
+impl Drop for BarrierWait<'_> {
+    fn drop(&mut self) {
+        if let Some((generation, id)) = self.waiting {
+            let mut state = self.barrier.state.lock().unwrap();
+            if state.generation == generation {
+                state.arrived -= 1;
+                state.wakers.remove(&id);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BarrierWait<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("BarrierWait { .. }")
+    }
+}
+
+/// Returned by [`Barrier::wait`] when all tasks have called it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Returns `true` if this task was the one that released the barrier.
+    ///
+    /// Only one task receives `true` for every release of the barrier.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
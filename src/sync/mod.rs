@@ -0,0 +1,59 @@
+//! Asynchronous synchronization primitives.
+//!
+//! The primitives in this module don't depend on any runtime, so they work with every
+//! executor supported by agnostik. Waiting tasks are suspended and woken up using their
+//! [`Waker`](std::task::Waker), and are served in FIFO order.
+//!
+//! In contrast to the primitives in [`std::sync`], the guards returned by the locks
+//! can be held across `.await` points. The `*_owned` variants return guards and permits
+//! that are bound to an [`Arc`](std::sync::Arc), so they can be moved into tasks
+//! created by [`spawn`](crate::spawn).
+//!
+//! ```ignore
+//! use agnostik::sync::Mutex;
+//! use std::sync::Arc;
+//!
+//! agnostik::block_on(async {
+//!     let counter = Arc::new(Mutex::new(0));
+//!
+//!     let guard = counter.clone().lock_owned().await;
+//!     let handle = agnostik::spawn(async move {
+//!         let mut guard = guard;
+//!         *guard += 1;
+//!     });
+//!
+//!     handle.await;
+//!     assert_eq!(*counter.lock().await, 1);
+//! });
+//! ```
+
+mod barrier;
+mod mutex;
+mod notify;
+mod once_cell;
+mod rwlock;
+mod semaphore;
+
+pub use barrier::{Barrier, BarrierWait, BarrierWaitResult};
+pub use mutex::{Mutex, MutexGuard, OwnedMutexGuard};
+pub use notify::{Notified, Notify};
+pub use once_cell::OnceCell;
+pub use rwlock::{
+    OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+pub use semaphore::{OwnedSemaphorePermit, Semaphore, SemaphorePermit, TryAcquireError};
+
+use std::{error::Error, fmt};
+
+/// Error returned by the `try_*` methods of [`Mutex`] and [`RwLock`],
+/// if the lock could not be acquired immediately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryLockError(());
+
+impl fmt::Display for TryLockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("operation would block")
+    }
+}
+
+impl Error for TryLockError {}
@@ -0,0 +1,191 @@
+use super::{Semaphore, TryLockError};
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+/// An asynchronous mutual exclusion lock.
+///
+/// In contrast to [`std::sync::Mutex`], the guard of this lock can be held across
+/// `.await` points, and waiting for the lock doesn't block the thread.
+/// The lock is fair: tasks acquire the lock in the order in which they called [`lock`].
+///
+/// [`lock`]: Mutex::lock
+pub struct Mutex<T: ?Sized> {
+    semaphore: Semaphore,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    /// Creates a new lock in an unlocked state.
+    pub fn new(value: T) -> Self {
+        Self {
+            semaphore: Semaphore::new(1),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the mutex, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Locks this mutex, waiting until it is available.
+    ///
+    /// The lock is released when the returned guard is dropped.
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        self.semaphore.acquire_raw(1).await;
+        MutexGuard { lock: self }
+    }
+
+    /// Attempts to acquire the lock, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is currently held, or other tasks are waiting for it.
+    pub fn try_lock(&self) -> Result<MutexGuard<'_, T>, TryLockError> {
+        if self.semaphore.try_acquire_raw(1) {
+            Ok(MutexGuard { lock: self })
+        } else {
+            Err(TryLockError(()))
+        }
+    }
+
+    /// Locks this mutex, and returns a guard that is bound to the `Arc`
+    /// instead of a reference.
+    ///
+    /// The returned guard can be moved into a spawned task.
+    pub async fn lock_owned(self: Arc<Self>) -> OwnedMutexGuard<T> {
+        self.semaphore.acquire_raw(1).await;
+        OwnedMutexGuard { lock: self }
+    }
+
+    /// Attempts to acquire the lock, and returns a guard that is bound to the `Arc`
+    /// instead of a reference.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is currently held, or other tasks are waiting for it.
+    pub fn try_lock_owned(self: Arc<Self>) -> Result<OwnedMutexGuard<T>, TryLockError> {
+        if self.semaphore.try_acquire_raw(1) {
+            Ok(OwnedMutexGuard { lock: self })
+        } else {
+            Err(TryLockError(()))
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// No locking is needed, because the mutable borrow statically guarantees
+    /// that no guard exists.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for Mutex<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Mutex");
+        match self.try_lock() {
+            Ok(guard) => d.field("data", &&*guard),
+            Err(_) => d.field("data", &format_args!("<locked>")),
+        };
+        d.finish()
+    }
+}
+
+/// A guard that releases the lock of a [`Mutex`] when dropped.
+#[must_use = "if unused the Mutex will immediately unlock"]
+pub struct MutexGuard<'a, T: ?Sized> {
+    lock: &'a Mutex<T>,
+}
+
+unsafe impl<T: ?Sized + Send + Sync> Sync for MutexGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.semaphore.release(1);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// An owned guard that releases the lock of a [`Mutex`] when dropped.
+///
+/// Created by [`Mutex::lock_owned`].
+#[must_use = "if unused the Mutex will immediately unlock"]
+pub struct OwnedMutexGuard<T: ?Sized> {
+    lock: Arc<Mutex<T>>,
+}
+
+unsafe impl<T: ?Sized + Send + Sync> Sync for OwnedMutexGuard<T> {}
+
+impl<T: ?Sized> OwnedMutexGuard<T> {
+    /// Returns a reference to the mutex that is locked by this guard.
+    pub fn mutex(this: &Self) -> &Arc<Mutex<T>> {
+        &this.lock
+    }
+}
+
+impl<T: ?Sized> Deref for OwnedMutexGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for OwnedMutexGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for OwnedMutexGuard<T> {
+    fn drop(&mut self) {
+        self.lock.semaphore.release(1);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for OwnedMutexGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
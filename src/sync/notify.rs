@@ -0,0 +1,210 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Mutex as StdMutex,
+    task::{Context, Poll, Waker},
+};
+
+/// Notifies a single task, or all waiting tasks, to wake up.
+///
+/// [`notify_one`] wakes up the task that waits the longest, or stores a permit
+/// if no task is waiting, so the next call to [`notified`] completes immediately.
+/// [`notify_waiters`] wakes up all tasks that are currently waiting, without
+/// storing a permit.
+///
+/// [`notify_one`]: Notify::notify_one
+/// [`notify_waiters`]: Notify::notify_waiters
+/// [`notified`]: Notify::notified
+pub struct Notify {
+    state: StdMutex<State>,
+}
+
+struct State {
+    permit: bool,
+    next_id: u64,
+    queue: VecDeque<u64>,
+    waiters: HashMap<u64, Waiter>,
+}
+
+struct Waiter {
+    waker: Option<Waker>,
+    notified: Option<Notification>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Notification {
+    One,
+    All,
+}
+
+impl State {
+    fn notify_one(&mut self) -> Option<Waker> {
+        if let Some(id) = self.queue.pop_front() {
+            let waiter = self.waiters.get_mut(&id).expect("queued waiter is missing");
+            waiter.notified = Some(Notification::One);
+            waiter.waker.take()
+        } else {
+            self.permit = true;
+            None
+        }
+    }
+}
+
+impl Notify {
+    /// Creates a new `Notify`, without a stored permit.
+    pub fn new() -> Self {
+        Self {
+            state: StdMutex::new(State {
+                permit: false,
+                next_id: 0,
+                queue: VecDeque::new(),
+                waiters: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Waits for a notification.
+    ///
+    /// The returned future is registered as a waiter when it's polled for the first time.
+    /// If a permit is stored at that time, it's consumed and the future completes immediately.
+    pub fn notified(&self) -> Notified<'_> {
+        Notified {
+            notify: self,
+            id: None,
+        }
+    }
+
+    /// Notifies the task that waits the longest.
+    ///
+    /// If no task is waiting, a permit is stored, which will be consumed by
+    /// the next call to [`notified`](Notify::notified). At most one permit can be stored.
+    pub fn notify_one(&self) {
+        let waker = self.state.lock().unwrap().notify_one();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Notifies all tasks that are currently waiting.
+    ///
+    /// No permit is stored, so tasks that start waiting after this call
+    /// will not be notified.
+    pub fn notify_waiters(&self) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            let State { queue, waiters, .. } = &mut *state;
+            queue
+                .drain(..)
+                .filter_map(|id| {
+                    let waiter = waiters.get_mut(&id).expect("queued waiter is missing");
+                    waiter.notified = Some(Notification::All);
+                    waiter.waker.take()
+                })
+                .collect::<Vec<_>>()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Notify {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("Notify")
+            .field("permit", &state.permit)
+            .field("waiters", &state.queue.len())
+            .finish()
+    }
+}
+
+/// Future returned by [`Notify::notified`].
+///
+/// If it's dropped after it was chosen by [`Notify::notify_one`], but before it completed,
+/// the notification is passed on to the next waiter.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Notified<'a> {
+    notify: &'a Notify,
+    id: Option<u64>,
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.notify.state.lock().unwrap();
+
+        let id = match self.id {
+            Some(id) => id,
+            None if state.permit => {
+                state.permit = false;
+                return Poll::Ready(());
+            }
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.waiters.insert(
+                    id,
+                    Waiter {
+                        waker: Some(cx.waker().clone()),
+                        notified: None,
+                    },
+                );
+                state.queue.push_back(id);
+                drop(state);
+
+                self.id = Some(id);
+                return Poll::Pending;
+            }
+        };
+
+        let waiter = state.waiters.get_mut(&id).expect("waiter is missing");
+        if waiter.notified.is_some() {
+            state.waiters.remove(&id);
+            drop(state);
+            self.id = None;
+            return Poll::Ready(());
+        }
+
+        match &waiter.waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => waiter.waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Notified<'_> {
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+
+        let waker = {
+            let mut state = self.notify.state.lock().unwrap();
+            let waiter = state.waiters.remove(&id).expect("waiter is missing");
+            match waiter.notified {
+                None => {
+                    state.queue.retain(|queued| *queued != id);
+                    None
+                }
+                Some(Notification::One) => state.notify_one(),
+                Some(Notification::All) => None,
+            }
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl fmt::Debug for Notified<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Notified { .. }")
+    }
+}
@@ -0,0 +1,196 @@
+use super::Semaphore;
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    future::Future,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A thread-safe cell that can be written to only once, with asynchronous initialization.
+///
+/// If multiple tasks call [`get_or_init`](OnceCell::get_or_init) at the same time,
+/// only one of them runs its initialization future, while the others wait for it.
+/// If the initialization future is dropped, the next waiting task runs its own.
+pub struct OnceCell<T> {
+    initialized: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+    semaphore: Semaphore,
+}
+
+unsafe impl<T: Send> Send for OnceCell<T> {}
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+
+impl<T> OnceCell<T> {
+    /// Creates a new, uninitialized cell.
+    pub fn new() -> Self {
+        Self {
+            initialized: AtomicBool::new(false),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            semaphore: Semaphore::new(1),
+        }
+    }
+
+    /// Returns `true` if the cell has been initialized.
+    pub fn initialized(&self) -> bool {
+        self.initialized.load(Ordering::Acquire)
+    }
+
+    /// Returns a reference to the value, if the cell has been initialized.
+    pub fn get(&self) -> Option<&T> {
+        if self.initialized() {
+            Some(unsafe { self.get_unchecked() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the value, if the cell has been initialized.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if self.initialized() {
+            Some(unsafe { &mut *(*self.value.get()).as_mut_ptr() })
+        } else {
+            None
+        }
+    }
+
+    /// Sets the value of the cell.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back, if the cell is already initialized or
+    /// another task is currently initializing it.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        if self.initialized() || !self.semaphore.try_acquire_raw(1) {
+            return Err(value);
+        }
+
+        let res = if self.initialized() {
+            Err(value)
+        } else {
+            unsafe { self.set_unchecked(value) };
+            Ok(())
+        };
+        self.semaphore.release(1);
+        res
+    }
+
+    /// Returns a reference to the value of the cell, and initializes it
+    /// using the future returned by `f` if the cell is empty.
+    pub async fn get_or_init<F, Fut>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        match self
+            .get_or_try_init(|| async { Ok::<_, std::convert::Infallible>(f().await) })
+            .await
+        {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Returns a reference to the value of the cell, and initializes it
+    /// using the future returned by `f` if the cell is empty.
+    ///
+    /// # Errors
+    ///
+    /// If the initialization future fails, the cell stays uninitialized
+    /// and the error is returned.
+    pub async fn get_or_try_init<E, F, Fut>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        // the permit is given back when this future completes or is dropped,
+        // so the next waiting task can check the cell again
+        let _permit = PermitGuard::new(self).await;
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        let value = f().await?;
+        unsafe { self.set_unchecked(value) };
+        Ok(unsafe { self.get_unchecked() })
+    }
+
+    /// Takes the value out of the cell, leaving it uninitialized.
+    pub fn take(&mut self) -> Option<T> {
+        if self.initialized() {
+            *self.initialized.get_mut() = false;
+            Some(unsafe { (*self.value.get()).as_ptr().read() })
+        } else {
+            None
+        }
+    }
+
+    /// Consumes the cell, returning the value if it has been initialized.
+    pub fn into_inner(mut self) -> Option<T> {
+        self.take()
+    }
+
+    /// # Safety
+    ///
+    /// The cell must be initialized.
+    unsafe fn get_unchecked(&self) -> &T {
+        &*(*self.value.get()).as_ptr()
+    }
+
+    /// # Safety
+    ///
+    /// The caller must hold the permit of the semaphore, and the cell
+    /// must be uninitialized.
+    unsafe fn set_unchecked(&self, value: T) {
+        (*self.value.get()).as_mut_ptr().write(value);
+        self.initialized.store(true, Ordering::Release);
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<T> for OnceCell<T> {
+    fn from(value: T) -> Self {
+        let cell = Self::new();
+        unsafe { cell.set_unchecked(value) };
+        cell
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        drop(self.take());
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnceCell")
+            .field("value", &self.get())
+            .finish()
+    }
+}
+
+/// Holds the single permit of the semaphore of a `OnceCell`.
+struct PermitGuard<'a>(&'a Semaphore);
+
+impl<'a> PermitGuard<'a> {
+    async fn new<T>(cell: &'a OnceCell<T>) -> PermitGuard<'a> {
+        cell.semaphore.acquire_raw(1).await;
+        PermitGuard(&cell.semaphore)
+    }
+}
+
+impl Drop for PermitGuard<'_> {
+    fn drop(&mut self) {
+        self.0.release(1);
+    }
+}
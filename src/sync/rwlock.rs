@@ -0,0 +1,191 @@
+use super::{Semaphore, TryLockError};
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+/// The number of permits a writer acquires, which is the maximum number
+/// of concurrent readers.
+const MAX_READS: usize = Semaphore::MAX_PERMITS;
+
+/// An asynchronous reader-writer lock.
+///
+/// The lock allows any number of readers or at most one writer at a time.
+/// It is fair: readers and writers acquire the lock in the order in which
+/// they requested it, so writers can't be starved by a constant stream of readers.
+pub struct RwLock<T: ?Sized> {
+    semaphore: Semaphore,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    /// Creates a new lock in an unlocked state.
+    pub fn new(value: T) -> Self {
+        Self {
+            semaphore: Semaphore::new(MAX_READS),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Locks this lock with shared read access, waiting until it can be acquired.
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.semaphore.acquire_raw(1).await;
+        RwLockReadGuard { lock: self }
+    }
+
+    /// Attempts to acquire shared read access, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a writer holds the lock, or other tasks are waiting for it.
+    pub fn try_read(&self) -> Result<RwLockReadGuard<'_, T>, TryLockError> {
+        if self.semaphore.try_acquire_raw(1) {
+            Ok(RwLockReadGuard { lock: self })
+        } else {
+            Err(TryLockError(()))
+        }
+    }
+
+    /// Locks this lock with exclusive write access, waiting until it can be acquired.
+    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.semaphore.acquire_raw(MAX_READS).await;
+        RwLockWriteGuard { lock: self }
+    }
+
+    /// Attempts to acquire exclusive write access, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is held, or other tasks are waiting for it.
+    pub fn try_write(&self) -> Result<RwLockWriteGuard<'_, T>, TryLockError> {
+        if self.semaphore.try_acquire_raw(MAX_READS) {
+            Ok(RwLockWriteGuard { lock: self })
+        } else {
+            Err(TryLockError(()))
+        }
+    }
+
+    /// Locks this lock with shared read access, and returns a guard that is bound
+    /// to the `Arc` instead of a reference.
+    pub async fn read_owned(self: Arc<Self>) -> OwnedRwLockReadGuard<T> {
+        self.semaphore.acquire_raw(1).await;
+        OwnedRwLockReadGuard { lock: self }
+    }
+
+    /// Locks this lock with exclusive write access, and returns a guard that is bound
+    /// to the `Arc` instead of a reference.
+    pub async fn write_owned(self: Arc<Self>) -> OwnedRwLockWriteGuard<T> {
+        self.semaphore.acquire_raw(MAX_READS).await;
+        OwnedRwLockWriteGuard { lock: self }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// No locking is needed, because the mutable borrow statically guarantees
+    /// that no guard exists.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for RwLock<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RwLock");
+        match self.try_read() {
+            Ok(guard) => d.field("data", &&*guard),
+            Err(_) => d.field("data", &format_args!("<locked>")),
+        };
+        d.finish()
+    }
+}
+
+macro_rules! guard {
+    ($(#[$attr:meta])* $name:ident$(<$lt:lifetime>)?, $lock:ty, $permits:expr, mut: $mutable:tt) => {
+        $(#[$attr])*
+        pub struct $name<$($lt,)? T: ?Sized> {
+            lock: $lock,
+        }
+
+        impl<$($lt,)? T: ?Sized> Deref for $name<$($lt,)? T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                unsafe { &*self.lock.value.get() }
+            }
+        }
+
+        guard!(@mut $mutable $name$(<$lt>)?);
+
+        impl<$($lt,)? T: ?Sized> Drop for $name<$($lt,)? T> {
+            fn drop(&mut self) {
+                self.lock.semaphore.release($permits);
+            }
+        }
+
+        impl<$($lt,)? T: ?Sized + fmt::Debug> fmt::Debug for $name<$($lt,)? T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&**self, f)
+            }
+        }
+    };
+    (@mut true $name:ident$(<$lt:lifetime>)?) => {
+        impl<$($lt,)? T: ?Sized> DerefMut for $name<$($lt,)? T> {
+            fn deref_mut(&mut self) -> &mut T {
+                unsafe { &mut *self.lock.value.get() }
+            }
+        }
+    };
+    (@mut false $name:ident$(<$lt:lifetime>)?) => {};
+}
+
+guard!(
+    /// A guard that releases the shared read access of a [`RwLock`] when dropped.
+    #[must_use = "if unused the RwLock will immediately unlock"]
+    RwLockReadGuard<'a>, &'a RwLock<T>, 1, mut: false
+);
+
+guard!(
+    /// A guard that releases the exclusive write access of a [`RwLock`] when dropped.
+    #[must_use = "if unused the RwLock will immediately unlock"]
+    RwLockWriteGuard<'a>, &'a RwLock<T>, MAX_READS, mut: true
+);
+
+guard!(
+    /// An owned guard that releases the shared read access of a [`RwLock`] when dropped.
+    ///
+    /// Created by [`RwLock::read_owned`].
+    #[must_use = "if unused the RwLock will immediately unlock"]
+    OwnedRwLockReadGuard, Arc<RwLock<T>>, 1, mut: false
+);
+
+guard!(
+    /// An owned guard that releases the exclusive write access of a [`RwLock`] when dropped.
+    ///
+    /// Created by [`RwLock::write_owned`].
+    #[must_use = "if unused the RwLock will immediately unlock"]
+    OwnedRwLockWriteGuard, Arc<RwLock<T>>, MAX_READS, mut: true
+);
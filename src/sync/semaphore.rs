@@ -0,0 +1,370 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A counting semaphore that performs asynchronous permit acquisition.
+///
+/// The semaphore is fair: permits are handed out in the order in which
+/// they were requested, even if a request for fewer permits could be
+/// satisfied earlier.
+pub struct Semaphore {
+    state: StdMutex<State>,
+}
+
+struct State {
+    permits: usize,
+    next_id: u64,
+    /// The ids of the waiters that still need permits, in FIFO order.
+    queue: VecDeque<u64>,
+    waiters: HashMap<u64, Waiter>,
+}
+
+struct Waiter {
+    needed: usize,
+    assigned: usize,
+    waker: Option<Waker>,
+}
+
+impl State {
+    /// Hands out the available permits to the queued waiters, and returns the
+    /// wakers of all waiters that got all of their permits.
+    fn assign(&mut self) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        while let Some(id) = self.queue.front() {
+            if self.permits == 0 {
+                break;
+            }
+
+            let waiter = self.waiters.get_mut(id).expect("queued waiter is missing");
+            let assign = self.permits.min(waiter.needed - waiter.assigned);
+            waiter.assigned += assign;
+            self.permits -= assign;
+
+            if waiter.assigned < waiter.needed {
+                break;
+            }
+            wakers.extend(waiter.waker.take());
+            self.queue.pop_front();
+        }
+        wakers
+    }
+}
+
+impl Semaphore {
+    /// The maximum number of permits which a semaphore can hold.
+    pub const MAX_PERMITS: usize = usize::MAX >> 3;
+
+    /// Creates a new semaphore with the initial number of permits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `permits` exceeds [`Semaphore::MAX_PERMITS`].
+    pub fn new(permits: usize) -> Self {
+        assert!(
+            permits <= Self::MAX_PERMITS,
+            "a semaphore may not have more than MAX_PERMITS permits"
+        );
+
+        Self {
+            state: StdMutex::new(State {
+                permits,
+                next_id: 0,
+                queue: VecDeque::new(),
+                waiters: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the current number of available permits.
+    pub fn available_permits(&self) -> usize {
+        self.state.lock().unwrap().permits
+    }
+
+    /// Adds `n` new permits to the semaphore.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of permits would exceed [`Semaphore::MAX_PERMITS`].
+    pub fn add_permits(&self, n: usize) {
+        self.release(n);
+    }
+
+    /// Acquires a permit from the semaphore.
+    ///
+    /// If there are no permits available, or other tasks are already waiting for
+    /// permits, the current task waits until a permit is released.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.acquire_many(1).await
+    }
+
+    /// Acquires `n` permits from the semaphore.
+    pub async fn acquire_many(&self, n: usize) -> SemaphorePermit<'_> {
+        self.acquire_raw(n).await;
+        SemaphorePermit {
+            semaphore: self,
+            permits: n,
+        }
+    }
+
+    /// Tries to acquire a permit from the semaphore, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are not enough permits available,
+    /// or other tasks are already waiting for permits.
+    pub fn try_acquire(&self) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        self.try_acquire_many(1)
+    }
+
+    /// Tries to acquire `n` permits from the semaphore, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are not enough permits available,
+    /// or other tasks are already waiting for permits.
+    pub fn try_acquire_many(&self, n: usize) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        if self.try_acquire_raw(n) {
+            Ok(SemaphorePermit {
+                semaphore: self,
+                permits: n,
+            })
+        } else {
+            Err(TryAcquireError(()))
+        }
+    }
+
+    /// Acquires a permit from the semaphore, and returns a permit
+    /// that is bound to the `Arc` instead of a reference.
+    ///
+    /// The returned permit can be moved into a spawned task.
+    pub async fn acquire_owned(self: Arc<Self>) -> OwnedSemaphorePermit {
+        self.acquire_many_owned(1).await
+    }
+
+    /// Acquires `n` permits from the semaphore, and returns a permit
+    /// that is bound to the `Arc` instead of a reference.
+    pub async fn acquire_many_owned(self: Arc<Self>, n: usize) -> OwnedSemaphorePermit {
+        self.acquire_raw(n).await;
+        OwnedSemaphorePermit {
+            semaphore: self,
+            permits: n,
+        }
+    }
+
+    /// Tries to acquire a permit from the semaphore, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no permits available,
+    /// or other tasks are already waiting for permits.
+    pub fn try_acquire_owned(self: Arc<Self>) -> Result<OwnedSemaphorePermit, TryAcquireError> {
+        if self.try_acquire_raw(1) {
+            Ok(OwnedSemaphorePermit {
+                semaphore: self,
+                permits: 1,
+            })
+        } else {
+            Err(TryAcquireError(()))
+        }
+    }
+
+    pub(crate) fn acquire_raw(&self, permits: usize) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            permits,
+            id: None,
+        }
+    }
+
+    pub(crate) fn try_acquire_raw(&self, permits: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.queue.is_empty() && state.permits >= permits {
+            state.permits -= permits;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn release(&self, permits: usize) {
+        if permits == 0 {
+            return;
+        }
+
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            assert!(
+                state.permits + permits <= Self::MAX_PERMITS,
+                "a semaphore may not have more than MAX_PERMITS permits"
+            );
+            state.permits += permits;
+            state.assign()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl fmt::Debug for Semaphore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("Semaphore")
+            .field("permits", &state.permits)
+            .field("waiters", &state.queue.len())
+            .finish()
+    }
+}
+
+/// Future that waits for permits of a semaphore.
+///
+/// If it's dropped before completion, the permits that were already
+/// assigned to it are given back to the semaphore.
+pub(crate) struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+    id: Option<u64>,
+}
+
+impl Future for Acquire<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.semaphore.state.lock().unwrap();
+
+        if let Some(id) = self.id {
+            let waiter = state.waiters.get_mut(&id).expect("waiter is missing");
+            if waiter.assigned == waiter.needed {
+                state.waiters.remove(&id);
+                drop(state);
+                self.id = None;
+                return Poll::Ready(());
+            }
+
+            match &waiter.waker {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                _ => waiter.waker = Some(cx.waker().clone()),
+            }
+            return Poll::Pending;
+        }
+
+        if state.queue.is_empty() && state.permits >= self.permits {
+            state.permits -= self.permits;
+            return Poll::Ready(());
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiters.insert(
+            id,
+            Waiter {
+                needed: self.permits,
+                assigned: 0,
+                waker: Some(cx.waker().clone()),
+            },
+        );
+        state.queue.push_back(id);
+
+        // the new waiter may be at the front of the queue, so it can take the
+        // permits that are currently available
+        let wakers = state.assign();
+        drop(state);
+        self.id = Some(id);
+        wakers.into_iter().for_each(Waker::wake);
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+
+        let wakers = {
+            let mut state = self.semaphore.state.lock().unwrap();
+            let waiter = state.waiters.remove(&id).expect("waiter is missing");
+            if waiter.assigned < waiter.needed {
+                state.queue.retain(|queued| *queued != id);
+            }
+
+            state.permits += waiter.assigned;
+            state.assign()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// A permit from the semaphore.
+///
+/// The permits are given back to the semaphore when this is dropped.
+#[must_use = "the permit is released immediately if it's not used"]
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+}
+
+impl SemaphorePermit<'_> {
+    /// Forgets the permit **without** releasing it back to the semaphore.
+    pub fn forget(mut self) {
+        self.permits = 0;
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.release(self.permits);
+    }
+}
+
+impl fmt::Debug for SemaphorePermit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SemaphorePermit")
+            .field("permits", &self.permits)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An owned permit from the semaphore.
+///
+/// The permits are given back to the semaphore when this is dropped.
+#[must_use = "the permit is released immediately if it's not used"]
+pub struct OwnedSemaphorePermit {
+    semaphore: Arc<Semaphore>,
+    permits: usize,
+}
+
+impl OwnedSemaphorePermit {
+    /// Forgets the permit **without** releasing it back to the semaphore.
+    pub fn forget(mut self) {
+        self.permits = 0;
+    }
+}
+
+impl Drop for OwnedSemaphorePermit {
+    fn drop(&mut self) {
+        self.semaphore.release(self.permits);
+    }
+}
+
+impl fmt::Debug for OwnedSemaphorePermit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnedSemaphorePermit")
+            .field("permits", &self.permits)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`Semaphore::try_acquire`] if there are not enough permits available.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryAcquireError(());
+
+impl fmt::Display for TryAcquireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no permits available")
+    }
+}
+
+impl Error for TryAcquireError {}
@@ -0,0 +1,122 @@
+use super::{trace, JoinError};
+use std::{
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+/// Wraps the future, so it can be aborted using the returned [`AbortHandle`].
+pub(crate) fn abortable<F: Future>(future: F) -> (Abortable<F>, AbortHandle) {
+    let state = Arc::new(AbortState {
+        aborted: AtomicBool::new(false),
+        finished: AtomicBool::new(false),
+        waker: StdMutex::new(None),
+    });
+    let handle = AbortHandle {
+        state: state.clone(),
+    };
+    (Abortable { future, state }, handle)
+}
+
+struct AbortState {
+    aborted: AtomicBool,
+    finished: AtomicBool,
+    waker: StdMutex<Option<Waker>>,
+}
+
+/// A handle to abort a spawned task.
+///
+/// Aborting a task stops it the next time it's polled, and drops its future.
+/// Awaiting the task afterwards returns a cancelled [`JoinError`].
+/// Tasks that already finished are not affected.
+#[derive(Clone)]
+pub struct AbortHandle {
+    state: Arc<AbortState>,
+}
+
+impl AbortHandle {
+    /// Aborts the task.
+    pub fn abort(&self) {
+        self.state.aborted.store(true, Ordering::SeqCst);
+        let waker = self.state.waker.lock().unwrap().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if the task finished, either by completing, panicking or being aborted.
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::SeqCst)
+    }
+}
+
+impl fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbortHandle")
+            .field("aborted", &self.state.aborted.load(Ordering::SeqCst))
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// A future that can be aborted by an [`AbortHandle`], and that catches panics of the inner future.
+#[pin_project::pin_project(PinnedDrop)]
+pub(crate) struct Abortable<F> {
+    #[pin]
+    future: F,
+    state: Arc<AbortState>,
+}
+
+impl<F: Future> Future for Abortable<F> {
+    type Output = Result<F::Output, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if this.state.aborted.load(Ordering::SeqCst) {
+            this.state.finished.store(true, Ordering::SeqCst);
+            trace::aborted();
+            return Poll::Ready(Err(JoinError::cancelled()));
+        }
+
+        {
+            let mut waker = this.state.waker.lock().unwrap();
+            match &*waker {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                _ => *waker = Some(cx.waker().clone()),
+            }
+        }
+
+        // the abort flag may have been set before the waker was stored
+        if this.state.aborted.load(Ordering::SeqCst) {
+            this.state.finished.store(true, Ordering::SeqCst);
+            trace::aborted();
+            return Poll::Ready(Err(JoinError::cancelled()));
+        }
+
+        let future = this.future;
+        let result = match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => return Poll::Pending,
+            Ok(Poll::Ready(output)) => Ok(output),
+            Err(payload) => {
+                trace::panicked(&payload);
+                Err(JoinError::panic(payload))
+            }
+        };
+
+        this.state.finished.store(true, Ordering::SeqCst);
+        Poll::Ready(result)
+    }
+}
+
+#[pin_project::pinned_drop]
+impl<F> PinnedDrop for Abortable<F> {
+    fn drop(self: Pin<&mut Self>) {
+        self.state.finished.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,141 @@
+use super::{hooks::Contexts, local::Inherit, LocalKey, Task};
+use crate::{join_handle::JoinHandle, shutdown};
+use std::{fmt, future::Future};
+
+/// Spawns tasks onto the global executor with custom options, like a name.
+///
+/// The name is available inside of the task using [`current`](super::current), is returned
+/// by [`JoinError::task_name`](super::JoinError::task_name) when awaiting the [`JoinHandle`]
+/// of a task that failed using [`try_join`](JoinHandle::try_join), and is passed to the
+/// async-std runtime, which shows it in its own panic messages. The name isn't passed to
+/// the tokio runtimes, which only support task names when they're built with the
+/// `tokio_unstable` cfg.
+///
+/// Task-local values are not available in spawned tasks, unless the task inherits
+/// them using [`inherit`](Builder::inherit).
+///
+/// ```ignore
+/// use agnostik::task::{self, Builder};
+///
+/// agnostik::block_on(async {
+///     let handle = Builder::new().name("ingest-worker").spawn(async {
+///         task::current().unwrap().name().map(String::from)
+///     });
+///     assert_eq!(handle.await.as_deref(), Some("ingest-worker"));
+/// });
+/// ```
+#[derive(Default, Clone)]
+pub struct Builder {
+    name: Option<String>,
+    inherit: Vec<&'static dyn Inherit>,
+}
+
+impl Builder {
+    /// Creates a new builder, which spawns unnamed tasks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the spawned tasks.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Lets the spawned tasks inherit the value of the task-local key.
+    ///
+    /// When a task is spawned, a copy of the key's current value is captured, which is
+    /// set while the task runs. Nothing is inherited if the key isn't set at that time.
+    ///
+    /// To let all spawned tasks inherit the value, register the key as a
+    /// [`SpawnHook`](super::SpawnHook) instead.
+    pub fn inherit<T: Clone + Send + 'static>(mut self, key: &'static LocalKey<T>) -> Self {
+        self.inherit.push(key);
+        self
+    }
+
+    /// Spawns an asynchronous task, like [`spawn`](crate::spawn).
+    #[track_caller]
+    pub fn spawn<F>(self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let future = self.inherited().scope(future);
+        shutdown::spawn(Task::new(self.name), future)
+    }
+
+    /// Spawns a blocking task, like [`spawn_blocking`](crate::spawn_blocking).
+    #[track_caller]
+    pub fn spawn_blocking<F, T>(self, task: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let mut inherited = self.inherited();
+        let task = move || inherited.enter(task);
+        shutdown::spawn_blocking(Task::new(self.name), task)
+    }
+
+    /// Spawns a `!Send` future onto the current thread, using
+    /// [`LocalAgnostikExecutor::spawn_local`](crate::LocalAgnostikExecutor::spawn_local).
+    ///
+    /// Local tasks are not tracked when the global executor is [shut down](crate::shutdown).
+    #[cfg(local_spawn)]
+    #[track_caller]
+    pub fn spawn_local<F>(self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let future = self.inherited().scope(future);
+        crate::EXECUTOR.spawn_local_task(Task::new(self.name), future)
+    }
+}
+
+impl Builder {
+    /// Captures the values of the inherited task-local keys.
+    fn inherited(&self) -> Contexts {
+        let mut contexts = Contexts::new();
+        for key in &self.inherit {
+            if let Some(context) = key.capture() {
+                contexts.push(context);
+            }
+        }
+        contexts
+    }
+}
+
+impl fmt::Debug for Builder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Spawns the future as the given task onto the global executor.
+#[cfg_attr(not(enable), allow(unused_variables, clippy::needless_pass_by_value))]
+pub(crate) fn spawn_task<F>(task: Task, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(enable)]
+    return crate::EXECUTOR.spawn_task(task, future);
+    #[cfg(not(enable))]
+    panic!("no runtime feature enabled.")
+}
+
+/// Runs the closure as the given task on a thread of the global executor for blocking tasks.
+#[cfg_attr(not(enable), allow(unused_variables, clippy::needless_pass_by_value))]
+pub(crate) fn spawn_blocking_task<F, T>(task: Task, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(enable)]
+    return crate::EXECUTOR.spawn_blocking_task(task, f);
+    #[cfg(not(enable))]
+    panic!("no runtime feature enabled.")
+}
@@ -0,0 +1,319 @@
+use super::{abort::abortable, JoinError, TaskGroup};
+use crate::{channel::PollFn, AgnostikExecutor};
+use futures_core::Stream;
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Runs `f` for every item of the stream, with at most `limit` spawned tasks in flight.
+///
+/// The tasks are spawned onto the global executor. Iterators can be passed by
+/// converting them into a stream using [`iter`].
+///
+/// If a future returns an error or its task fails, no more items are taken from
+/// the stream, the remaining tasks are aborted, and the error is returned.
+///
+/// # Errors
+///
+/// Returns the first error that occurred, together with the index of its item.
+///
+/// # Panics
+///
+/// Panics if `limit` is zero.
+pub async fn for_each_concurrent<S, F, Fut, E>(
+    stream: S,
+    limit: usize,
+    f: F,
+) -> Result<(), ConcurrentError<E>>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+    E: Send + 'static,
+{
+    for_each_concurrent_on(crate::executor(), stream, limit, f).await
+}
+
+/// Like [`for_each_concurrent`], but spawns the tasks onto the given executor.
+///
+/// # Errors
+///
+/// Returns the first error that occurred, together with the index of its item.
+///
+/// # Panics
+///
+/// Panics if `limit` is zero.
+pub async fn for_each_concurrent_on<X, S, F, Fut, E>(
+    executor: &X,
+    stream: S,
+    limit: usize,
+    f: F,
+) -> Result<(), ConcurrentError<E>>
+where
+    X: AgnostikExecutor,
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+    E: Send + 'static,
+{
+    run(executor, stream, limit, f, |_, ()| {}).await
+}
+
+/// Converts an iterator into a stream, so its items can be processed by
+/// [`for_each_concurrent`] and the `map_concurrent` functions.
+///
+/// ```ignore
+/// use agnostik::task;
+///
+/// let lengths = task::map_concurrent(task::iter(urls), 8, |url| async move {
+///     fetch(url).await.map(|body| body.len())
+/// })
+/// .await?;
+/// ```
+pub fn iter<I: IntoIterator>(iter: I) -> Iter<I::IntoIter> {
+    Iter {
+        iter: iter.into_iter(),
+    }
+}
+
+/// Stream returned by [`iter`], which yields the items of an iterator.
+#[derive(Clone, Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Iter<I> {
+    iter: I,
+}
+
+// the iterator is never pinned
+impl<I> Unpin for Iter<I> {}
+
+impl<I: Iterator> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Maps every item of the stream using `f`, with at most `limit` spawned tasks in flight,
+/// and returns the outputs in the order of the items.
+///
+/// The tasks are spawned onto the global executor. Errors are handled like in
+/// [`for_each_concurrent`].
+///
+/// # Errors
+///
+/// Returns the first error that occurred, together with the index of its item.
+///
+/// # Panics
+///
+/// Panics if `limit` is zero.
+pub async fn map_concurrent<S, F, Fut, T, E>(
+    stream: S,
+    limit: usize,
+    f: F,
+) -> Result<Vec<T>, ConcurrentError<E>>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    map_concurrent_on(crate::executor(), stream, limit, f).await
+}
+
+/// Like [`map_concurrent`], but spawns the tasks onto the given executor.
+///
+/// # Errors
+///
+/// Returns the first error that occurred, together with the index of its item.
+///
+/// # Panics
+///
+/// Panics if `limit` is zero.
+pub async fn map_concurrent_on<X, S, F, Fut, T, E>(
+    executor: &X,
+    stream: S,
+    limit: usize,
+    f: F,
+) -> Result<Vec<T>, ConcurrentError<E>>
+where
+    X: AgnostikExecutor,
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    let mut outputs = Vec::new();
+    run(executor, stream, limit, f, |index, output| {
+        if outputs.len() <= index {
+            outputs.resize_with(index + 1, || None);
+        }
+        outputs[index] = Some(output);
+    })
+    .await?;
+
+    Ok(outputs
+        .into_iter()
+        .map(|output| output.expect("every item produced an output"))
+        .collect())
+}
+
+/// Maps every item of the stream using `f`, with at most `limit` spawned tasks in flight,
+/// and returns the outputs in the order in which the tasks completed.
+///
+/// The tasks are spawned onto the global executor. Errors are handled like in
+/// [`for_each_concurrent`].
+///
+/// # Errors
+///
+/// Returns the first error that occurred, together with the index of its item.
+///
+/// # Panics
+///
+/// Panics if `limit` is zero.
+pub async fn map_concurrent_unordered<S, F, Fut, T, E>(
+    stream: S,
+    limit: usize,
+    f: F,
+) -> Result<Vec<T>, ConcurrentError<E>>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    map_concurrent_unordered_on(crate::executor(), stream, limit, f).await
+}
+
+/// Like [`map_concurrent_unordered`], but spawns the tasks onto the given executor.
+///
+/// # Errors
+///
+/// Returns the first error that occurred, together with the index of its item.
+///
+/// # Panics
+///
+/// Panics if `limit` is zero.
+pub async fn map_concurrent_unordered_on<X, S, F, Fut, T, E>(
+    executor: &X,
+    stream: S,
+    limit: usize,
+    f: F,
+) -> Result<Vec<T>, ConcurrentError<E>>
+where
+    X: AgnostikExecutor,
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    let mut outputs = Vec::new();
+    run(executor, stream, limit, f, |_, output| outputs.push(output)).await?;
+    Ok(outputs)
+}
+
+async fn run<X, S, F, Fut, T, E>(
+    executor: &X,
+    stream: S,
+    limit: usize,
+    mut f: F,
+    mut on_output: impl FnMut(usize, T),
+) -> Result<(), ConcurrentError<E>>
+where
+    X: AgnostikExecutor,
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    assert!(limit > 0, "the concurrency limit must be greater than zero");
+
+    let mut stream = Box::pin(stream);
+    // every item is spawned as a single task, so the index of the task is the item's index
+    let mut group = TaskGroup::<Result<Result<T, E>, JoinError>>::new();
+    let mut exhausted = false;
+
+    loop {
+        while !exhausted && group.len() < limit {
+            let next = PollFn(|cx: &mut Context<'_>| Pin::as_mut(&mut stream).poll_next(cx)).await;
+            let Some(item) = next else {
+                exhausted = true;
+                break;
+            };
+
+            // panics are caught inside of the task, so they're reported with their payload
+            let (future, _) = abortable(f(item));
+            group.spawn_on(executor, future);
+        }
+
+        let Some((index, result)) = group.join_next_with_index().await else {
+            return Ok(());
+        };
+
+        // the executor may drop the task, e.g. when it's shut down, which fails the outer result
+        match result.and_then(|result| result) {
+            Ok(Ok(output)) => on_output(index, output),
+            Ok(Err(error)) => return Err(ConcurrentError::Failed { index, error }),
+            Err(error) => return Err(ConcurrentError::Join { index, error }),
+        }
+    }
+}
+
+/// Error returned by the concurrency helpers, like [`for_each_concurrent`].
+#[derive(Debug)]
+pub enum ConcurrentError<E> {
+    /// The future of an item returned an error.
+    Failed {
+        /// The index of the item in the stream.
+        index: usize,
+        /// The error returned by the future.
+        error: E,
+    },
+    /// The task of an item panicked or was cancelled.
+    Join {
+        /// The index of the item in the stream.
+        index: usize,
+        /// The reason why the task failed.
+        error: JoinError,
+    },
+}
+
+impl<E> ConcurrentError<E> {
+    /// Returns the index of the item that failed.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Failed { index, .. } | Self::Join { index, .. } => *index,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ConcurrentError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Failed { index, error } => write!(f, "item {} failed: {}", index, error),
+            Self::Join { index, error } => write!(f, "task of item {} failed: {}", index, error),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ConcurrentError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Failed { error, .. } => Some(error),
+            Self::Join { error, .. } => Some(error),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::{
+    hooks::Contexts,
+    trace::{Kind, TaskTrace},
+    unwind::PanicHandler,
+    Id,
+};
+use std::{
+    cell::RefCell,
+    fmt,
+    future::Future,
+    panic::Location,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Task>> = const { RefCell::new(None) };
+}
+
+/// Returns a handle to the task that is currently running.
+///
+/// Returns `None` if it's called outside of a spawned task.
+pub fn current() -> Option<Task> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// A handle to a spawned task, returned by [`current`].
+#[derive(Clone)]
+pub struct Task {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    id: Id,
+    name: Option<String>,
+    location: &'static Location<'static>,
+    /// The panic handler of the executor that the task is spawned onto, if it has one.
+    panic_handler: Option<PanicHandler>,
+}
+
+impl Task {
+    #[track_caller]
+    pub(crate) fn new(name: Option<String>) -> Self {
+        Self::with_panic_handler(name, None)
+    }
+
+    /// Creates a task, whose panics are reported to the given handler instead of the
+    /// global one.
+    #[track_caller]
+    pub(crate) fn with_panic_handler(name: Option<String>, handler: Option<PanicHandler>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                id: Id::next(),
+                name,
+                location: Location::caller(),
+                panic_handler: handler,
+            }),
+        }
+    }
+
+    /// Returns the [`Id`] of the task.
+    pub fn id(&self) -> Id {
+        self.inner.id
+    }
+
+    /// Returns the name of the task, if it was given one using a [`Builder`](super::Builder).
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name.as_deref()
+    }
+
+    /// Returns the location in the source code where the task was spawned.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.inner.location
+    }
+
+    pub(crate) fn panic_handler(&self) -> Option<&PanicHandler> {
+        self.inner.panic_handler.as_ref()
+    }
+
+    /// Runs the closure with this task set as the current task.
+    pub(crate) fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        /// Restores the previous task, even if the closure panics.
+        struct Reset(Option<Task>);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+            }
+        }
+
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(self.clone()));
+        let _reset = Reset(previous);
+        f()
+    }
+}
+
+impl fmt::Debug for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Task")
+            .field("id", &self.id())
+            .field("name", &self.name())
+            .field("location", &self.location())
+            .finish()
+    }
+}
+
+/// A future that is set as the current task whenever it's polled, and that
+/// enters the contexts captured by the [spawn hooks](super::SpawnHook).
+#[pin_project::pin_project]
+pub(crate) struct TaskFuture<F> {
+    #[pin]
+    future: F,
+    task: Task,
+    contexts: Contexts,
+    trace: TaskTrace,
+}
+
+#[cfg_attr(not(enable), allow(dead_code))]
+impl<F> TaskFuture<F> {
+    /// Wraps the future of a task, which is about to be spawned.
+    pub(crate) fn new(task: Task, future: F) -> Self {
+        Self::with_kind(task, future, Kind::Async)
+    }
+
+    /// Wraps the future of a local task, which is about to be spawned.
+    #[cfg(local_spawn)]
+    pub(crate) fn local(task: Task, future: F) -> Self {
+        Self::with_kind(task, future, Kind::Local)
+    }
+
+    fn with_kind(task: Task, future: F, kind: Kind) -> Self {
+        Self {
+            future,
+            trace: TaskTrace::new(&task, kind),
+            task,
+            contexts: Contexts::capture(),
+        }
+    }
+
+    /// Wraps the closure of a blocking task, which is about to be spawned.
+    pub(crate) fn blocking<T>(task: Task, f: F) -> impl FnOnce() -> T + Send + 'static
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let trace = TaskTrace::new(&task, Kind::Blocking);
+        let mut contexts = Contexts::capture();
+        move || trace.run(|| task.enter(|| contexts.enter(f)))
+    }
+}
+
+impl<F: Future> Future for TaskFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.project();
+        let future = this.future;
+        let contexts = this.contexts;
+        let task = this.task;
+        this.trace
+            .poll(|| task.enter(|| contexts.enter(|| future.poll(cx))))
+    }
+}
@@ -0,0 +1,220 @@
+use super::{
+    abort::{abortable, AbortHandle},
+    JoinError,
+};
+use crate::{channel::mpsc, join_handle::JoinHandle, AgnostikExecutor};
+use futures_core::Stream;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+type Report<T> = (usize, Result<T, JoinError>);
+
+/// A collection of spawned tasks, whose results are returned in the order in which
+/// the tasks complete.
+///
+/// Tasks are spawned using [`spawn`](TaskGroup::spawn) onto the global executor,
+/// or using [`spawn_on`](TaskGroup::spawn_on) onto any [`AgnostikExecutor`].
+/// Their results are returned by [`join_next`](TaskGroup::join_next), or by using
+/// the group as a [`Stream`].
+///
+/// When the group is dropped, all tasks that are still running are aborted.
+///
+/// ```ignore
+/// use agnostik::task::TaskGroup;
+///
+/// agnostik::block_on(async {
+///     let mut group = TaskGroup::new();
+///     for i in 0..10 {
+///         group.spawn(async move { i * 2 });
+///     }
+///
+///     let mut sum = 0;
+///     while let Some(result) = group.join_next().await {
+///         sum += result.unwrap();
+///     }
+///     assert_eq!(sum, 90);
+/// });
+/// ```
+pub struct TaskGroup<T> {
+    tasks: HashMap<usize, Entry>,
+    /// The index of the next spawned task, counting from zero.
+    next_id: usize,
+    tx: mpsc::UnboundedSender<Report<T>>,
+    rx: mpsc::UnboundedReceiver<Report<T>>,
+}
+
+struct Entry {
+    abort: AbortHandle,
+    /// Keeps the task alive on executors that cancel tasks whose handle is dropped.
+    _handle: JoinHandle<()>,
+}
+
+/// Reports the result of a task to its group.
+///
+/// If the task is dropped by the executor before it completed,
+/// a cancelled error is reported instead.
+struct Reporter<T> {
+    id: usize,
+    tx: mpsc::UnboundedSender<Report<T>>,
+    reported: bool,
+}
+
+impl<T> Reporter<T> {
+    fn report(mut self, result: Result<T, JoinError>) {
+        self.reported = true;
+        drop(self.tx.send((self.id, result)));
+    }
+}
+
+impl<T> Drop for Reporter<T> {
+    fn drop(&mut self) {
+        if !self.reported {
+            drop(self.tx.send((self.id, Err(JoinError::cancelled()))));
+        }
+    }
+}
+
+impl<T> TaskGroup<T> {
+    /// Creates an empty task group.
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            tasks: HashMap::new(),
+            next_id: 0,
+            tx,
+            rx,
+        }
+    }
+
+    /// Returns the number of tasks in the group, whose results weren't returned yet.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns `true` if the group contains no tasks.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Aborts all tasks in the group.
+    ///
+    /// The tasks stay in the group, and [`join_next`](TaskGroup::join_next) returns
+    /// a cancelled [`JoinError`] for every task that didn't complete before.
+    pub fn abort_all(&mut self) {
+        self.tasks.values().for_each(|entry| entry.abort.abort());
+    }
+
+    /// Waits until one of the tasks in the group completes, and returns its result.
+    ///
+    /// Returns `None` if the group is empty.
+    pub async fn join_next(&mut self) -> Option<Result<T, JoinError>> {
+        crate::channel::PollFn(|cx: &mut Context<'_>| self.poll_join_next(cx)).await
+    }
+
+    /// Polls for the next task in the group to complete.
+    pub fn poll_join_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, JoinError>>> {
+        self.poll_join_next_with_index(cx)
+            .map(|report| report.map(|(_, result)| result))
+    }
+
+    /// Like [`join_next`](TaskGroup::join_next), but also returns the index of the task,
+    /// which counts the tasks in the order in which they were spawned, starting at zero.
+    pub(crate) async fn join_next_with_index(&mut self) -> Option<Report<T>> {
+        crate::channel::PollFn(|cx: &mut Context<'_>| self.poll_join_next_with_index(cx)).await
+    }
+
+    fn poll_join_next_with_index(&mut self, cx: &mut Context<'_>) -> Poll<Option<Report<T>>> {
+        if self.tasks.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        match self.rx.poll_recv(cx) {
+            Poll::Ready(Some((id, result))) => {
+                self.tasks.remove(&id);
+                Poll::Ready(Some((id, result)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T: Send + 'static> TaskGroup<T> {
+    /// Spawns the future onto the global executor, and adds it to the group.
+    #[track_caller]
+    pub fn spawn<F>(&mut self, future: F) -> AbortHandle
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        self.spawn_on(crate::executor(), future)
+    }
+
+    /// Spawns the future onto the given executor, and adds it to the group.
+    #[cfg_attr(not(enable), allow(unreachable_code, unused_variables))]
+    #[track_caller]
+    pub fn spawn_on<E, F>(&mut self, executor: &E, future: F) -> AbortHandle
+    where
+        E: AgnostikExecutor,
+        F: Future<Output = T> + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let (task, abort) = abortable(future);
+        let reporter = Reporter {
+            id,
+            tx: self.tx.clone(),
+            reported: false,
+        };
+        let handle = executor.spawn(async move {
+            let result = task.await;
+            reporter.report(result);
+        });
+
+        self.tasks.insert(
+            id,
+            Entry {
+                abort: abort.clone(),
+                _handle: handle,
+            },
+        );
+        abort
+    }
+}
+
+impl<T> Default for TaskGroup<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for TaskGroup<T> {
+    fn drop(&mut self) {
+        self.abort_all();
+    }
+}
+
+impl<T> Stream for TaskGroup<T> {
+    type Item = Result<T, JoinError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_join_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl<T> fmt::Debug for TaskGroup<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskGroup")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,129 @@
+use once_cell::sync::Lazy;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+
+static HOOKS: Lazy<RwLock<Vec<Arc<dyn SpawnHook>>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// A hook that propagates context, like request ids or tracing spans, from a task
+/// to the tasks it spawns.
+///
+/// Whenever a task is spawned, [`capture`](SpawnHook::capture) is called in the spawning
+/// task, and the returned [`SpawnContext`] is entered around every poll of the spawned
+/// task, or around the closure of a blocking task. Hooks are applied by every executor.
+///
+/// Task-local keys whose value implements `Clone` are hooks as well, which makes
+/// all spawned tasks inherit their value.
+///
+/// ```ignore
+/// use agnostik::task;
+///
+/// agnostik::task_local! {
+///     static REQUEST_ID: u64;
+/// }
+///
+/// task::add_spawn_hook(&REQUEST_ID);
+///
+/// agnostik::block_on(REQUEST_ID.scope(42, async {
+///     let id = agnostik::spawn(async { REQUEST_ID.get() }).await;
+///     assert_eq!(id, 42);
+/// }));
+/// ```
+pub trait SpawnHook: Send + Sync + 'static {
+    /// Captures the context of the current task, when a new task is spawned.
+    ///
+    /// Returns `None` if there's nothing to propagate.
+    fn capture(&self) -> Option<Box<dyn SpawnContext>>;
+}
+
+/// Context captured by a [`SpawnHook`], which is restored in the spawned task.
+pub trait SpawnContext: Send + 'static {
+    /// Restores the context while `f` runs.
+    ///
+    /// Implementations must call `f` exactly once.
+    fn enter(&mut self, f: &mut dyn FnMut());
+}
+
+/// Registers a hook, which is applied to all tasks spawned afterwards.
+///
+/// The contexts of the hooks are entered in the order in which the hooks were registered,
+/// so the context of the first hook is the outermost one.
+pub fn add_spawn_hook(hook: impl SpawnHook) {
+    HOOKS.write().unwrap().push(Arc::new(hook));
+}
+
+/// Contexts that are restored while a spawned task runs.
+pub(crate) struct Contexts(Vec<Box<dyn SpawnContext>>);
+
+impl Contexts {
+    pub(crate) fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Captures the contexts of all registered hooks.
+    pub(crate) fn capture() -> Self {
+        let hooks = HOOKS.read().unwrap();
+        Self(hooks.iter().filter_map(|hook| hook.capture()).collect())
+    }
+
+    pub(crate) fn push(&mut self, context: Box<dyn SpawnContext>) {
+        self.0.push(context);
+    }
+
+    /// Enters all contexts while the closure runs.
+    pub(crate) fn enter<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        fn enter_all(contexts: &mut [Box<dyn SpawnContext>], f: &mut dyn FnMut()) {
+            match contexts.split_first_mut() {
+                Some((first, rest)) => first.enter(&mut || enter_all(rest, f)),
+                None => f(),
+            }
+        }
+
+        if self.0.is_empty() {
+            return f();
+        }
+
+        let mut f = Some(f);
+        let mut output = None;
+        enter_all(&mut self.0, &mut || {
+            output = f.take().map(|f| f());
+        });
+        output.expect("a `SpawnContext` didn't call its closure")
+    }
+
+    /// Wraps the future, so the contexts are entered while it's polled.
+    pub(crate) fn scope<F>(self, future: F) -> ContextFuture<F> {
+        ContextFuture {
+            future,
+            contexts: self,
+        }
+    }
+}
+
+impl fmt::Debug for Contexts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Contexts { .. }")
+    }
+}
+
+/// A future that enters its contexts while it's polled.
+#[pin_project::pin_project]
+pub(crate) struct ContextFuture<F> {
+    #[pin]
+    future: F,
+    contexts: Contexts,
+}
+
+impl<F: Future> Future for ContextFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.project();
+        let future = this.future;
+        this.contexts.enter(|| future.poll(cx))
+    }
+}
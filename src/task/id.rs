@@ -0,0 +1,49 @@
+use super::current;
+use std::{
+    fmt,
+    num::NonZeroU64,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// An identifier of a spawned task, which is unique for the lifetime of the process.
+///
+/// It can be retrieved inside of the task using [`id`], and outside of the task
+/// using [`JoinHandle::id`](crate::join_handle::JoinHandle::id).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Id(NonZeroU64);
+
+impl Id {
+    pub(crate) fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Self(NonZeroU64::new(id).expect("task ids are exhausted"))
+    }
+
+    /// Returns the id as a number.
+    pub fn as_u64(self) -> u64 {
+        self.0.get()
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Returns the [`Id`] of the task that is currently running.
+///
+/// # Panics
+///
+/// Panics if it's called outside of a spawned task. Use [`try_id`] instead, to
+/// handle this case.
+pub fn id() -> Id {
+    try_id().expect("`task::id` called outside of a task")
+}
+
+/// Returns the [`Id`] of the task that is currently running, or `None` if it's
+/// called outside of a spawned task.
+pub fn try_id() -> Option<Id> {
+    current().map(|task| task.id())
+}
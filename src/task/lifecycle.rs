@@ -0,0 +1,119 @@
+use std::{
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+/// Callbacks that are called during the lifecycle of a task, which is spawned using
+/// [`spawn_with_hooks`](crate::AgnostikExecutor::spawn_with_hooks).
+///
+/// The hooks are called from a wrapper around the task's future, so `before_start` is
+/// called once before the first poll. Bastion emulates the hooks the same way instead of
+/// using the callbacks of a lightproc `ProcStack`, which are called every time the task
+/// is run; those can still be registered using `BastionExecutor::spawn_with_stack`.
+///
+/// ```ignore
+/// use agnostik::{prelude::*, task::LifecycleHooks};
+///
+/// let hooks = LifecycleHooks::new()
+///     .before_start(|| println!("started"))
+///     .after_complete(|| println!("completed"))
+///     .after_panic(|| println!("panicked"));
+///
+/// agnostik::executor().spawn_with_hooks(hooks, async {});
+/// ```
+#[derive(Clone, Default)]
+pub struct LifecycleHooks {
+    before_start: Option<Callback>,
+    after_complete: Option<Callback>,
+    after_panic: Option<Callback>,
+}
+
+impl LifecycleHooks {
+    /// Creates hooks without any callbacks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls the closure before the task starts.
+    pub fn before_start(mut self, f: impl Fn() + Send + Sync + 'static) -> Self {
+        self.before_start = Some(Arc::new(f));
+        self
+    }
+
+    /// Calls the closure after the task completed.
+    pub fn after_complete(mut self, f: impl Fn() + Send + Sync + 'static) -> Self {
+        self.after_complete = Some(Arc::new(f));
+        self
+    }
+
+    /// Calls the closure after the task panicked.
+    pub fn after_panic(mut self, f: impl Fn() + Send + Sync + 'static) -> Self {
+        self.after_panic = Some(Arc::new(f));
+        self
+    }
+
+    /// Wraps the future, so the hooks are called when it's polled.
+    pub(crate) fn wrap<F>(self, future: F) -> Hooked<F> {
+        Hooked {
+            future,
+            hooks: self,
+            started: false,
+        }
+    }
+}
+
+impl fmt::Debug for LifecycleHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LifecycleHooks")
+            .field("before_start", &self.before_start.is_some())
+            .field("after_complete", &self.after_complete.is_some())
+            .field("after_panic", &self.after_panic.is_some())
+            .finish()
+    }
+}
+
+/// A future that calls the [`LifecycleHooks`] while it's polled.
+#[pin_project::pin_project]
+pub(crate) struct Hooked<F> {
+    #[pin]
+    future: F,
+    hooks: LifecycleHooks,
+    started: bool,
+}
+
+impl<F: Future> Future for Hooked<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.project();
+        if !*this.started {
+            *this.started = true;
+            if let Some(f) = &this.hooks.before_start {
+                f();
+            }
+        }
+
+        let future = this.future;
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => {
+                if let Some(f) = &this.hooks.after_complete {
+                    f();
+                }
+                Poll::Ready(output)
+            }
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => {
+                if let Some(f) = &this.hooks.after_panic {
+                    f();
+                }
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+}
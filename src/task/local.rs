@@ -0,0 +1,241 @@
+use super::{SpawnContext, SpawnHook};
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+};
+
+/// Declares task-local values, which are stored in a [`LocalKey`].
+///
+/// A value is only available inside of a [`scope`](LocalKey::scope), and can be accessed
+/// using [`with`](LocalKey::with) or [`try_with`](LocalKey::try_with). The values are
+/// stored independently of the runtime, so they behave the same on every executor.
+///
+/// ```ignore
+/// agnostik::task_local! {
+///     static REQUEST_ID: u64;
+/// }
+///
+/// agnostik::block_on(REQUEST_ID.scope(42, async {
+///     assert_eq!(REQUEST_ID.with(|id| *id), 42);
+/// }));
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+        $crate::task_local!($($rest)*);
+    };
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __task_local_inner {
+    ($(#[$attr:meta])* $vis:vis $name:ident, $t:ty) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task::LocalKey<$t> = {
+            std::thread_local! {
+                static __KEY: std::cell::RefCell<Option<$t>> = const { std::cell::RefCell::new(None) };
+            }
+
+            $crate::task::LocalKey { inner: __KEY }
+        };
+    };
+}
+
+/// A key for task-local values, declared using [`task_local!`](crate::task_local).
+pub struct LocalKey<T: 'static> {
+    #[doc(hidden)]
+    pub inner: thread::LocalKey<RefCell<Option<T>>>,
+}
+
+impl<T: 'static> LocalKey<T> {
+    /// Sets the value of the key while the future is polled.
+    ///
+    /// The value is dropped together with the returned future.
+    pub fn scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<T, F> {
+        TaskLocalFuture {
+            key: self,
+            slot: Some(value),
+            future: Some(future),
+        }
+    }
+
+    /// Sets the value of the key while the closure runs.
+    pub fn sync_scope<R>(&'static self, value: T, f: impl FnOnce() -> R) -> R {
+        self.enter(&mut Some(value), f)
+    }
+
+    /// Calls the closure with a reference to the value of the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it's called outside of a [`scope`](LocalKey::scope) of this key.
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        self.try_with(f)
+            .expect("cannot access a task-local value outside of its scope")
+    }
+
+    /// Calls the closure with a reference to the value of the key, if it's set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it's called outside of a [`scope`](LocalKey::scope) of this key.
+    pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Result<R, AccessError> {
+        self.inner
+            .try_with(|value| value.borrow().as_ref().map(f))
+            .ok()
+            .flatten()
+            .ok_or(AccessError(()))
+    }
+
+    /// Returns a copy of the value of the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it's called outside of a [`scope`](LocalKey::scope) of this key.
+    pub fn get(&'static self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    /// Moves the value of the slot into the key while the closure runs.
+    fn enter<R>(&'static self, slot: &mut Option<T>, f: impl FnOnce() -> R) -> R {
+        /// Moves the value back into the slot, even if the closure panics.
+        struct Reset<'a, T: 'static> {
+            key: &'static LocalKey<T>,
+            slot: &'a mut Option<T>,
+        }
+
+        impl<T: 'static> Drop for Reset<'_, T> {
+            fn drop(&mut self) {
+                self.key
+                    .inner
+                    .with(|value| mem::swap(self.slot, &mut *value.borrow_mut()));
+            }
+        }
+
+        self.inner.with(|value| {
+            let mut value = value
+                .try_borrow_mut()
+                .expect("cannot enter a task-local scope while its value is borrowed");
+            mem::swap(slot, &mut *value);
+        });
+
+        let _reset = Reset { key: self, slot };
+        f()
+    }
+}
+
+impl<T: 'static> fmt::Debug for LocalKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("LocalKey { .. }")
+    }
+}
+
+/// Future returned by [`LocalKey::scope`].
+#[pin_project::pin_project(PinnedDrop)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct TaskLocalFuture<T: 'static, F> {
+    key: &'static LocalKey<T>,
+    slot: Option<T>,
+    #[pin]
+    future: Option<F>,
+}
+
+impl<T: 'static, F: Future> Future for TaskLocalFuture<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.project();
+        let mut future = this.future;
+        let poll = this.key.enter(this.slot, || {
+            future
+                .as_mut()
+                .as_pin_mut()
+                .expect("`TaskLocalFuture` polled after completion")
+                .poll(cx)
+        });
+
+        if poll.is_ready() {
+            future.set(None);
+        }
+        poll
+    }
+}
+
+#[pin_project::pinned_drop]
+impl<T: 'static, F> PinnedDrop for TaskLocalFuture<T, F> {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        let mut future = this.future;
+        if future.is_some() {
+            // the future may access the value when it's dropped
+            this.key.enter(this.slot, || future.set(None));
+        }
+    }
+}
+
+impl<T: 'static, F> fmt::Debug for TaskLocalFuture<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("TaskLocalFuture { .. }")
+    }
+}
+
+/// Error returned by [`LocalKey::try_with`], if the value isn't set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessError(());
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("task-local value is not set")
+    }
+}
+
+impl Error for AccessError {}
+
+/// A task-local key, whose value can be inherited by spawned tasks.
+pub(crate) trait Inherit: Sync {
+    /// Captures a copy of the current value of the key.
+    fn capture(&'static self) -> Option<Box<dyn SpawnContext>>;
+}
+
+impl<T: Clone + Send + 'static> Inherit for LocalKey<T> {
+    fn capture(&'static self) -> Option<Box<dyn SpawnContext>> {
+        let value = self.try_with(T::clone).ok()?;
+        Some(Box::new(Captured {
+            key: self,
+            slot: Some(value),
+        }))
+    }
+}
+
+impl<T: Clone + Send + 'static> SpawnHook for &'static LocalKey<T> {
+    fn capture(&self) -> Option<Box<dyn SpawnContext>> {
+        Inherit::capture(*self)
+    }
+}
+
+struct Captured<T: 'static> {
+    key: &'static LocalKey<T>,
+    slot: Option<T>,
+}
+
+impl<T: Send + 'static> SpawnContext for Captured<T> {
+    fn enter(&mut self, f: &mut dyn FnMut()) {
+        self.key.enter(&mut self.slot, f);
+    }
+}
@@ -0,0 +1,177 @@
+//! Utilities for spawning and managing tasks.
+//!
+//! [`scope`] spawns tasks that may borrow from the enclosing stack frame, and
+//! waits until all of them finished, while [`scope_async`] does the same in an
+//! asynchronous context. [`TaskGroup`] manages a dynamic set of
+//! spawned tasks, and returns their results in completion order.
+//! [`for_each_concurrent`] and [`map_concurrent`] process the items of a stream, or of
+//! an iterator converted using [`iter`], in spawned tasks, while limiting how many of
+//! them are in flight at once.
+//! [`CancellationToken`] signals cancellation to a tree of tasks.
+//! [`Builder`] spawns tasks with a name, which is available using [`current`].
+//! Every spawned task has a unique [`Id`], which is returned by [`id`].
+//! [`task_local!`](crate::task_local) declares values that are local to a task.
+//! [`SpawnHook`]s propagate context from a task to the tasks it spawns.
+//! [`LifecycleHooks`] are called when a single task starts, completes or panics.
+//! [`on_task_panic`] sets the global handler, which is called whenever a task panics.
+//! [`Priority`] decides which tasks spawned using
+//! [`spawn_with_priority`](crate::AgnostikExecutor::spawn_with_priority) run first.
+
+mod abort;
+mod builder;
+mod concurrent;
+mod current;
+mod group;
+mod hooks;
+mod id;
+mod lifecycle;
+mod local;
+mod priority;
+mod scope;
+mod token;
+pub(crate) mod trace;
+mod unwind;
+
+pub use abort::AbortHandle;
+pub(crate) use abort::abortable;
+pub use builder::Builder;
+pub(crate) use builder::{spawn_blocking_task, spawn_task};
+pub use concurrent::{
+    for_each_concurrent, for_each_concurrent_on, iter, map_concurrent, map_concurrent_on,
+    map_concurrent_unordered, map_concurrent_unordered_on, ConcurrentError, Iter,
+};
+pub use current::{current, Task};
+pub(crate) use current::TaskFuture;
+pub use group::TaskGroup;
+pub use hooks::{add_spawn_hook, SpawnContext, SpawnHook};
+pub use id::{id, try_id, Id};
+pub use lifecycle::LifecycleHooks;
+pub use local::{AccessError, LocalKey, TaskLocalFuture};
+pub(crate) use priority::spawn as spawn_with_priority;
+pub use priority::{set_priority_workers, Priority};
+#[cfg(enable)]
+pub(crate) use priority::{spawn_on as spawn_with_priority_on, Scheduler as PriorityScheduler};
+pub use scope::{scope, scope_async, Scope, ScopeFuture, ScopedJoinHandle};
+pub use token::{CancellationToken, DropGuard, RunUntilCancelled, WaitForCancellation};
+#[cfg(any(async_std, bastion))]
+pub(crate) use unwind::remote;
+pub(crate) use unwind::CatchUnwind;
+#[cfg(bastion)]
+pub(crate) use unwind::Joinable;
+#[cfg(enable)]
+pub(crate) use unwind::PanicHandler;
+pub use unwind::{on_task_panic, PanicPayload};
+
+use std::{any::Any, error::Error, fmt, panic};
+
+/// Error returned when a task didn't complete successfully.
+pub struct JoinError {
+    repr: Repr,
+    task: Option<Task>,
+}
+
+enum Repr {
+    Cancelled,
+    Panic(Box<dyn Any + Send + 'static>),
+}
+
+impl JoinError {
+    pub(crate) fn cancelled() -> Self {
+        Self {
+            repr: Repr::Cancelled,
+            task: None,
+        }
+    }
+
+    pub(crate) fn panic(payload: Box<dyn Any + Send + 'static>) -> Self {
+        Self {
+            repr: Repr::Panic(payload),
+            task: None,
+        }
+    }
+
+    /// Attaches the task that failed, so its name is included in the error message.
+    pub(crate) fn with_task(mut self, task: Task) -> Self {
+        self.task = Some(task);
+        self
+    }
+
+    /// Resumes the panic of the task with its original payload, or panics if the task
+    /// was cancelled.
+    pub(crate) fn resume(self) -> ! {
+        match self.repr {
+            Repr::Panic(payload) => panic::resume_unwind(payload),
+            Repr::Cancelled => match self.task.as_ref().and_then(Task::name) {
+                Some(name) => panic!("task '{}' was cancelled", name),
+                None => panic!("task was cancelled"),
+            },
+        }
+    }
+
+    /// Returns the name of the task that failed, if it was spawned with a name using
+    /// [`Builder`].
+    ///
+    /// The name is only known for tasks that were spawned using the global functions.
+    pub fn task_name(&self) -> Option<&str> {
+        self.task.as_ref().and_then(Task::name)
+    }
+
+    /// Returns `true` if the task was aborted or cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.repr, Repr::Cancelled)
+    }
+
+    /// Returns `true` if the task panicked.
+    pub fn is_panic(&self) -> bool {
+        matches!(self.repr, Repr::Panic(_))
+    }
+
+    /// Returns the payload of the panic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the task didn't panic, but was cancelled.
+    pub fn into_panic(self) -> Box<dyn Any + Send + 'static> {
+        self.try_into_panic()
+            .expect("`JoinError` reason is not a panic")
+    }
+
+    /// Returns the payload of the panic, if the task panicked.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error back if the task was cancelled.
+    pub fn try_into_panic(self) -> Result<Box<dyn Any + Send + 'static>, Self> {
+        match self.repr {
+            Repr::Panic(payload) => Ok(payload),
+            repr @ Repr::Cancelled => Err(Self {
+                repr,
+                task: self.task,
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.repr {
+            Repr::Cancelled => f.pad("JoinError::Cancelled"),
+            Repr::Panic(_) => f.pad("JoinError::Panic(..)"),
+        }
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("task ")?;
+        if let Some(name) = self.task.as_ref().and_then(Task::name) {
+            write!(f, "'{}' ", name)?;
+        }
+        match self.repr {
+            Repr::Cancelled => f.write_str("was cancelled"),
+            Repr::Panic(_) => f.write_str("panicked"),
+        }
+    }
+}
+
+impl Error for JoinError {}
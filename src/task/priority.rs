@@ -0,0 +1,308 @@
+use super::{unwind::remote, Task};
+use crate::{join_handle::JoinHandle, AgnostikExecutor};
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    num::NonZeroUsize,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex, Weak,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread,
+};
+
+/// How many tasks a worker polls, before it yields to the executor.
+const BUDGET: usize = 64;
+
+static MAX_WORKERS: Lazy<AtomicUsize> =
+    Lazy::new(|| AtomicUsize::new(thread::available_parallelism().map_or(1, NonZeroUsize::get)));
+
+/// The scheduler of the global executor, whose backend executors all spawn onto the
+/// same runtime.
+static SCHEDULER: Lazy<Arc<Scheduler>> = Lazy::new(Scheduler::new);
+
+/// The priority of a task spawned using
+/// [`spawn_with_priority`](crate::AgnostikExecutor::spawn_with_priority).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Only runs when no task with a higher priority is ready.
+    Low,
+    /// The priority of tasks, that don't need a specific priority.
+    #[default]
+    Normal,
+    /// Runs before all tasks with a lower priority, that are ready.
+    High,
+}
+
+impl Priority {
+    fn index(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+/// Sets how many worker tasks run the prioritized tasks at most.
+///
+/// The workers are spawned onto the executor, whenever a prioritized task is spawned
+/// and fewer workers are running, and exit once all prioritized tasks finished.
+/// The limit applies to every executor separately, and defaults to the number of CPUs.
+///
+/// # Panics
+///
+/// Panics if `workers` is zero.
+pub fn set_priority_workers(workers: usize) {
+    assert!(workers > 0, "at least one worker is required");
+    MAX_WORKERS.store(workers, Ordering::SeqCst);
+}
+
+/// The priority-aware scheduling layer of an executor.
+///
+/// The global executor has a single scheduler, and every [`Pool`](crate::Pool) has its
+/// own, so the prioritized tasks of different executors never run on each other's workers.
+/// Every prioritized task is queued in the run queue of its priority whenever it's woken,
+/// and the workers always poll the task at the front of the queue with the highest priority.
+pub(crate) struct Scheduler {
+    state: StdMutex<State>,
+}
+
+struct State {
+    /// The run queues of the priorities, from high to low.
+    queues: [VecDeque<Arc<Job>>; 3],
+    /// All tasks that didn't finish yet, so they can be dropped when the scheduler is closed.
+    jobs: HashMap<u64, Weak<Job>>,
+    next_id: u64,
+    workers: usize,
+    /// The wakers of the workers, that are waiting for a task to run.
+    idle: Vec<Waker>,
+    closed: bool,
+}
+
+impl Scheduler {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: StdMutex::new(State {
+                queues: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+                jobs: HashMap::new(),
+                next_id: 0,
+                workers: 0,
+                idle: Vec::new(),
+                closed: false,
+            }),
+        })
+    }
+
+    /// Drops the prioritized tasks that didn't finish yet, and the ones that are spawned
+    /// later, so their handles resolve to a cancelled [`JoinError`](super::JoinError).
+    #[cfg(enable)]
+    pub(crate) fn close(&self) {
+        let jobs = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            state.queues.iter_mut().for_each(VecDeque::clear);
+            state.wake_idle();
+            state.jobs.drain().map(|(_, job)| job).collect::<Vec<_>>()
+        };
+
+        // tasks that are polled right now are dropped by their worker afterwards
+        for job in jobs.iter().filter_map(Weak::upgrade) {
+            job.cancel();
+        }
+    }
+}
+
+impl State {
+    fn pop(&mut self) -> Option<Arc<Job>> {
+        self.queues.iter_mut().find_map(VecDeque::pop_front)
+    }
+
+    fn wake_idle(&mut self) {
+        self.idle.drain(..).for_each(Waker::wake);
+    }
+}
+
+/// A prioritized task.
+struct Job {
+    id: u64,
+    priority: Priority,
+    scheduler: Weak<Scheduler>,
+    future: StdMutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>,
+    /// Set while the task is in a run queue, so it's only queued once.
+    queued: AtomicBool,
+}
+
+impl Job {
+    fn schedule(self: &Arc<Self>) {
+        if self.queued.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Some(scheduler) = self.scheduler.upgrade() {
+            let mut state = scheduler.state.lock().unwrap();
+            if state.closed {
+                drop(state);
+                self.cancel();
+                return;
+            }
+            state.queues[self.priority.index()].push_back(Arc::clone(self));
+            if let Some(worker) = state.idle.pop() {
+                drop(state);
+                worker.wake();
+            }
+        }
+    }
+
+    /// Drops the future, unless it's polled right now.
+    fn cancel(&self) {
+        if let Ok(mut future) = self.future.try_lock() {
+            future.take();
+        }
+    }
+
+    /// Polls the task once.
+    fn run(self: Arc<Self>, scheduler: &Scheduler) {
+        self.queued.store(false, Ordering::SeqCst);
+        let waker = Waker::from(Arc::clone(&self));
+        let mut slot = self.future.lock().unwrap();
+        let finished = slot.as_mut().map_or(false, |future| {
+            future
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+        });
+
+        let mut state = scheduler.state.lock().unwrap();
+        if finished || state.closed {
+            state.jobs.remove(&self.id);
+            if state.jobs.is_empty() {
+                state.wake_idle();
+            }
+            drop(state);
+            slot.take();
+        }
+    }
+}
+
+impl Wake for Job {
+    fn wake(self: Arc<Self>) {
+        self.schedule();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.schedule();
+    }
+}
+
+/// A worker task, which polls the prioritized tasks until all of them finished.
+struct Worker {
+    scheduler: Arc<Scheduler>,
+    /// Set once the worker exited, so it isn't counted twice when it's dropped.
+    exited: bool,
+}
+
+impl Future for Worker {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        for _ in 0..BUDGET {
+            let job = {
+                let mut state = this.scheduler.state.lock().unwrap();
+                match state.pop() {
+                    Some(job) => job,
+                    None if state.jobs.is_empty() => {
+                        state.workers -= 1;
+                        this.exited = true;
+                        return Poll::Ready(());
+                    }
+                    None => {
+                        state.idle.push(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            };
+            job.run(&this.scheduler);
+        }
+
+        // give the other tasks of the executor a chance to run
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // the worker was dropped by its executor, so a new one is spawned for the next task,
+        // and the other workers register again, in case they waited behind its waker
+        if !self.exited {
+            let mut state = self.scheduler.state.lock().unwrap();
+            state.workers -= 1;
+            state.wake_idle();
+        }
+    }
+}
+
+/// Spawns the future as a prioritized task of the global executor, and spawns a worker
+/// onto the executor if needed.
+#[track_caller]
+pub(crate) fn spawn<E, F>(executor: &E, priority: Priority, future: F) -> JoinHandle<F::Output>
+where
+    E: AgnostikExecutor + ?Sized,
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    spawn_on(&SCHEDULER, executor, Task::new(None), priority, future)
+}
+
+/// Spawns the future as the given prioritized task of the scheduler, and spawns a worker
+/// onto the executor, which owns the scheduler, if needed.
+pub(crate) fn spawn_on<E, F>(
+    scheduler: &Arc<Scheduler>,
+    executor: &E,
+    task: Task,
+    priority: Priority,
+    future: F,
+) -> JoinHandle<F::Output>
+where
+    E: AgnostikExecutor + ?Sized,
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (future, handle) = remote(task, future);
+    let (job, spawn_worker) = {
+        let mut state = scheduler.state.lock().unwrap();
+        if state.closed {
+            // dropping the future resolves the handle as cancelled
+            return handle;
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        let job = Arc::new(Job {
+            id,
+            priority,
+            scheduler: Arc::downgrade(scheduler),
+            future: StdMutex::new(Some(Box::pin(future))),
+            queued: AtomicBool::new(false),
+        });
+        state.jobs.insert(id, Arc::downgrade(&job));
+        let spawn_worker = state.workers < MAX_WORKERS.load(Ordering::SeqCst);
+        if spawn_worker {
+            state.workers += 1;
+        }
+        (job, spawn_worker)
+    };
+    job.schedule();
+    if spawn_worker {
+        let worker = Worker {
+            scheduler: Arc::clone(scheduler),
+            exited: false,
+        };
+        executor.spawn(worker).detach();
+    }
+
+    handle
+}
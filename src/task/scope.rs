@@ -0,0 +1,389 @@
+use crate::{channel::oneshot, join_handle::JoinHandle, AgnostikExecutor};
+use std::{
+    any::Any,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A child future, with its lifetime erased.
+type Child = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Creates a scope in which tasks can be spawned that borrow from the enclosing stack,
+/// and blocks the current thread until all of them finished.
+///
+/// The closure receives a [`Scope`], which spawns child tasks onto the global
+/// executor using [`AgnostikExecutor::spawn`]. In contrast to [`spawn`](crate::spawn),
+/// the spawned futures only have to live as long as `'env`. Like
+/// [`std::thread::scope`], the function only returns once the future returned by the
+/// closure and all child tasks have finished, so the borrows can't outlive the scope.
+///
+/// If a child task panics, the remaining children are cancelled and the panic is
+/// resumed on the current thread.
+///
+/// The children run on the global executor, while the current thread is blocked, so
+/// the function has to be called where [`spawn`](crate::spawn) works. Inside of an
+/// asynchronous task, call it using [`spawn_blocking`](crate::spawn_blocking), or use
+/// [`scope_async`] instead.
+///
+/// ```ignore
+/// let values = vec![1, 2, 3];
+/// let total = std::sync::atomic::AtomicUsize::new(0);
+/// let (values_ref, total_ref) = (&values, &total);
+///
+/// agnostik::scope(|s| async move {
+///     for value in values_ref {
+///         s.spawn(async move {
+///             total_ref.fetch_add(*value, std::sync::atomic::Ordering::SeqCst);
+///         });
+///     }
+/// });
+/// assert_eq!(total.into_inner(), 6);
+/// ```
+pub fn scope<'env, F, Fut>(f: F) -> Fut::Output
+where
+    F: FnOnce(Scope<'env>) -> Fut,
+    Fut: Future,
+{
+    // SAFETY: the scope future is owned by `block_on`, which polls it to completion, or
+    // drops it when a panic unwinds, so it's never leaked
+    crate::channel::block_on(unsafe { scope_async(f) })
+}
+
+/// Creates a scope in which tasks can be spawned that borrow from the enclosing stack,
+/// and returns a future that waits until all of them finished.
+///
+/// This is the asynchronous version of [`scope`]. The returned future completes with
+/// the output of the future returned by the closure, once that future and all child
+/// tasks have finished. If a child task panics, the remaining children are cancelled
+/// and the panic is resumed when the scope future is polled. Dropping the scope future
+/// cancels all children that are still running, and waits until the children that are
+/// currently polled by the executor are dropped.
+///
+/// ```ignore
+/// let values = vec![1, 2, 3];
+///
+/// let sum = agnostik::block_on(async move {
+///     let total = std::sync::atomic::AtomicUsize::new(0);
+///     let (values, total_ref) = (&values, &total);
+///
+///     // SAFETY: the scope future is awaited, so it's never leaked
+///     unsafe {
+///         agnostik::task::scope_async(|s| async move {
+///             for value in values {
+///                 let total = total_ref;
+///                 s.spawn(async move {
+///                     total.fetch_add(*value, std::sync::atomic::Ordering::SeqCst);
+///                 });
+///             }
+///         })
+///     }
+///     .await;
+///
+///     total.into_inner()
+/// });
+/// assert_eq!(sum, 6);
+/// ```
+///
+/// # Safety
+///
+/// The child tasks run on the executor while they borrow data that lives for `'env`,
+/// and only the scope future keeps them from outliving it: it cancels them when it's
+/// dropped. The borrow checker ends `'env` once the scope future can't be used
+/// anymore, which doesn't imply that it was dropped, so the caller must guarantee
+/// that the destructor of the scope future runs before `'env` ends, unless the future
+/// completed. The closure is called right away and may already spawn children, so the
+/// future must not be
+///
+/// - leaked, e.g. using [`mem::forget`], [`Box::leak`] or a reference cycle of `Arc`s,
+/// - moved into a value that is leaked, or that outlives `'env` in another way, e.g. a
+///   task spawned onto an executor that is never polled to completion.
+///
+/// Awaiting the future directly, like in the example above, or dropping it, is always
+/// sound.
+pub unsafe fn scope_async<'env, F, Fut>(f: F) -> ScopeFuture<'env, Fut>
+where
+    F: FnOnce(Scope<'env>) -> Fut,
+    Fut: Future,
+{
+    // the guard cancels the children if the closure panics after spawning some of them
+    let guard = CancelOnDrop(Arc::new(Shared {
+        state: StdMutex::new(State {
+            children: Vec::new(),
+            handles: Vec::new(),
+            running: 0,
+            cancelled: false,
+            panic: None,
+            waker: None,
+        }),
+    }));
+
+    let scope = Scope {
+        shared: guard.0.clone(),
+        _env: PhantomData,
+    };
+    let body = f(scope);
+    ScopeFuture {
+        guard,
+        body,
+        output: None,
+        done: false,
+        _env: PhantomData,
+    }
+}
+
+struct Shared {
+    state: StdMutex<State>,
+}
+
+struct State {
+    children: Vec<Arc<StdMutex<Option<Child>>>>,
+    handles: Vec<JoinHandle<()>>,
+    running: usize,
+    cancelled: bool,
+    panic: Option<Box<dyn Any + Send>>,
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    fn finished(&self, panic: Option<Box<dyn Any + Send>>) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.running -= 1;
+            if state.panic.is_none() {
+                state.panic = panic;
+            }
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Drops all children, waiting for children that are currently polled.
+    fn cancel(&self) {
+        let (children, handles) = {
+            let mut state = self.state.lock().unwrap();
+            state.cancelled = true;
+            (
+                mem::take(&mut state.children),
+                mem::take(&mut state.handles),
+            )
+        };
+
+        for child in children {
+            let mut slot = child.lock().unwrap();
+            drop(slot.take());
+        }
+        drop(handles);
+    }
+}
+
+/// Cancels the children of a scope when it's dropped.
+struct CancelOnDrop(Arc<Shared>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// A handle to spawn tasks inside of a [`scope`].
+///
+/// The handle can be cloned and moved into child tasks, to spawn more children
+/// from within them.
+pub struct Scope<'env> {
+    shared: Arc<Shared>,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'env> Scope<'env> {
+    /// Spawns a child task, which may borrow from `'env`.
+    ///
+    /// If the scope is already cancelled, the future is dropped without being spawned.
+    #[cfg_attr(not(enable), allow(unreachable_code, unused_variables))]
+    #[track_caller]
+    pub fn spawn<Fut>(&self, future: Fut) -> ScopedJoinHandle<Fut::Output>
+    where
+        Fut: Future + Send + 'env,
+        Fut::Output: Send + 'env,
+    {
+        let (tx, rx) = oneshot::channel();
+        let child: Pin<Box<dyn Future<Output = ()> + Send + 'env>> = Box::pin(async move {
+            let output = future.await;
+            drop(tx.send(output));
+        });
+        // SAFETY: the caller of `scope_async` guarantees that the scope future is dropped
+        // before `'env` ends, which drops all children.
+        let child = unsafe {
+            mem::transmute::<Pin<Box<dyn Future<Output = ()> + Send + 'env>>, Child>(child)
+        };
+
+        let slot = Arc::new(StdMutex::new(Some(child)));
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            if state.cancelled {
+                drop(state);
+                drop(slot);
+                return ScopedJoinHandle { rx };
+            }
+            state.children.push(slot.clone());
+            state.running += 1;
+        }
+
+        let handle = crate::executor().spawn(ChildTask {
+            slot,
+            shared: self.shared.clone(),
+        });
+        self.shared.state.lock().unwrap().handles.push(handle);
+        ScopedJoinHandle { rx }
+    }
+}
+
+impl Clone for Scope<'_> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            _env: PhantomData,
+        }
+    }
+}
+
+impl fmt::Debug for Scope<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.shared.state.lock().unwrap();
+        f.debug_struct("Scope")
+            .field("running", &state.running)
+            .field("cancelled", &state.cancelled)
+            .finish()
+    }
+}
+
+/// The task that is spawned onto the executor for every child.
+///
+/// The child future is stored behind a lock, so the scope can drop it
+/// without racing with the executor polling it.
+struct ChildTask {
+    slot: Arc<StdMutex<Option<Child>>>,
+    shared: Arc<Shared>,
+}
+
+impl Future for ChildTask {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut slot = self.slot.lock().unwrap();
+        let Some(child) = slot.as_mut() else {
+            return Poll::Ready(());
+        };
+
+        let panic = match panic::catch_unwind(AssertUnwindSafe(|| child.as_mut().poll(cx))) {
+            Ok(Poll::Pending) => return Poll::Pending,
+            Ok(Poll::Ready(())) => None,
+            Err(payload) => Some(payload),
+        };
+
+        *slot = None;
+        drop(slot);
+        self.shared.finished(panic);
+        Poll::Ready(())
+    }
+}
+
+impl Drop for ChildTask {
+    fn drop(&mut self) {
+        // the child must be dropped while holding the lock, so a cancelling
+        // scope waits until it's gone
+        let mut slot = self.slot.lock().unwrap();
+        if let Some(child) = slot.take() {
+            drop(child);
+            drop(slot);
+            self.shared.finished(None);
+        }
+    }
+}
+
+/// Future returned by [`scope_async`].
+///
+/// Dropping it cancels all child tasks that are still running.
+#[pin_project::pin_project]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ScopeFuture<'env, Fut: Future> {
+    /// Declared first, so the children are cancelled before the body is dropped.
+    guard: CancelOnDrop,
+    #[pin]
+    body: Fut,
+    output: Option<Fut::Output>,
+    done: bool,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<Fut: Future> Future for ScopeFuture<'_, Fut> {
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Fut::Output> {
+        let this = self.project();
+        if !*this.done {
+            if let Poll::Ready(output) = this.body.poll(cx) {
+                *this.output = Some(output);
+                *this.done = true;
+            }
+        }
+
+        let shared = &this.guard.0;
+        let mut state = shared.state.lock().unwrap();
+        if let Some(payload) = state.panic.take() {
+            drop(state);
+            shared.cancel();
+            panic::resume_unwind(payload);
+        }
+
+        if *this.done && state.running == 0 {
+            if let Some(output) = this.output.take() {
+                return Poll::Ready(output);
+            }
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<Fut: Future> fmt::Debug for ScopeFuture<'_, Fut> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopeFuture")
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle that awaits the output of a task spawned by [`Scope::spawn`].
+///
+/// If the child panics or is cancelled, the handle never completes. In that
+/// case the panic is propagated by the scope instead.
+pub struct ScopedJoinHandle<T> {
+    rx: oneshot::Receiver<T>,
+}
+
+impl<T> Future for ScopedJoinHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        match Pin::new(&mut self.rx).poll(cx) {
+            Poll::Ready(Ok(output)) => Poll::Ready(output),
+            Poll::Ready(Err(_)) | Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> fmt::Debug for ScopedJoinHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("ScopedJoinHandle { .. }")
+    }
+}
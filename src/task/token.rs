@@ -0,0 +1,256 @@
+use crate::channel::waiters::WaitQueue;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A token to signal cancellation to a tree of tasks.
+///
+/// Cloned tokens share the same state, so cancelling any clone cancels all of them.
+/// [Child tokens](CancellationToken::child_token) are cancelled together with their
+/// parent, but cancelling a child doesn't affect its parent.
+///
+/// Tasks can wait for cancellation using [`cancelled`](CancellationToken::cancelled),
+/// or can be spawned using [`AgnostikExecutor::spawn_with_token`](crate::AgnostikExecutor::spawn_with_token),
+/// which stops them as soon as the token is cancelled.
+///
+/// ```ignore
+/// use agnostik::{task::CancellationToken, AgnostikExecutor};
+///
+/// agnostik::block_on(async {
+///     let token = CancellationToken::new();
+///     let handle = agnostik::executor().spawn_with_token(&token.child_token(), async {
+///         futures::future::pending::<()>().await
+///     });
+///
+///     token.cancel();
+///     assert_eq!(handle.await, None);
+/// });
+/// ```
+#[derive(Clone)]
+pub struct CancellationToken {
+    node: Arc<Node>,
+}
+
+struct Node {
+    state: StdMutex<State>,
+}
+
+struct State {
+    cancelled: bool,
+    /// The children are kept alive by their parent, so the cancellation still reaches
+    /// the grandchildren of a child whose tokens were all dropped.
+    children: Vec<Arc<Node>>,
+    waiters: WaitQueue,
+}
+
+impl Node {
+    fn new(cancelled: bool) -> Arc<Self> {
+        Arc::new(Self {
+            state: StdMutex::new(State {
+                cancelled,
+                children: Vec::new(),
+                waiters: WaitQueue::new(),
+            }),
+        })
+    }
+
+    fn cancel(&self) {
+        let (children, wakers) = {
+            let mut state = self.state.lock().unwrap();
+            if state.cancelled {
+                return;
+            }
+            state.cancelled = true;
+            (
+                std::mem::take(&mut state.children),
+                state.waiters.notify_all(),
+            )
+        };
+
+        wakers.into_iter().for_each(Waker::wake);
+        for child in children {
+            child.cancel();
+        }
+    }
+
+    /// Returns `true` if a token of the node, or of one of its descendants, is still alive,
+    /// and forgets about the descendants that can't be cancelled anymore.
+    fn is_alive(node: &Arc<Node>) -> bool {
+        // the parent holds one reference, every token another one
+        if Arc::strong_count(node) > 1 {
+            return true;
+        }
+        let mut state = node.state.lock().unwrap();
+        state.children.retain(Node::is_alive);
+        !state.children.is_empty()
+    }
+}
+
+impl CancellationToken {
+    /// Creates a new token, which isn't cancelled.
+    pub fn new() -> Self {
+        Self {
+            node: Node::new(false),
+        }
+    }
+
+    /// Creates a child token, which is cancelled when this token is cancelled.
+    ///
+    /// If this token is already cancelled, the child is cancelled as well.
+    pub fn child_token(&self) -> Self {
+        let mut state = self.node.state.lock().unwrap();
+        let child = Node::new(state.cancelled);
+        if !state.cancelled {
+            // forget about children that were already dropped
+            state.children.retain(Node::is_alive);
+            state.children.push(child.clone());
+        }
+        Self { node: child }
+    }
+
+    /// Cancels the token, and all of its children.
+    ///
+    /// All tasks waiting for cancellation are woken up.
+    pub fn cancel(&self) {
+        self.node.cancel();
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.node.state.lock().unwrap().cancelled
+    }
+
+    /// Waits until the token is cancelled.
+    pub fn cancelled(&self) -> WaitForCancellation<'_> {
+        WaitForCancellation {
+            token: self,
+            waiter: None,
+        }
+    }
+
+    /// Runs the future until it completes, or until the token is cancelled.
+    ///
+    /// Returns `None` if the token was cancelled first, in which case the future is dropped.
+    pub fn run_until_cancelled<F: Future>(&self, future: F) -> RunUntilCancelled<'_, F> {
+        RunUntilCancelled {
+            future,
+            cancelled: self.cancelled(),
+        }
+    }
+
+    /// Returns a guard that cancels the token when it's dropped.
+    pub fn drop_guard(self) -> DropGuard {
+        DropGuard { token: Some(self) }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WaitForCancellation<'a> {
+    token: &'a CancellationToken,
+    waiter: Option<u64>,
+}
+
+impl Future for WaitForCancellation<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+        let mut state = this.token.node.state.lock().unwrap();
+        if state.cancelled {
+            state.waiters.remove(&mut this.waiter);
+            return Poll::Ready(());
+        }
+
+        state.waiters.register(&mut this.waiter, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl Drop for WaitForCancellation<'_> {
+    fn drop(&mut self) {
+        if self.waiter.is_some() {
+            let mut state = self.token.node.state.lock().unwrap();
+            state.waiters.remove(&mut self.waiter);
+        }
+    }
+}
+
+impl fmt::Debug for WaitForCancellation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("WaitForCancellation { .. }")
+    }
+}
+
+/// Future returned by [`CancellationToken::run_until_cancelled`].
+#[pin_project::pin_project]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RunUntilCancelled<'a, F> {
+    #[pin]
+    future: F,
+    cancelled: WaitForCancellation<'a>,
+}
+
+impl<F: Future> Future for RunUntilCancelled<'_, F> {
+    type Output = Option<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if Pin::new(this.cancelled).poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        this.future.poll(cx).map(Some)
+    }
+}
+
+impl<F> fmt::Debug for RunUntilCancelled<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("RunUntilCancelled { .. }")
+    }
+}
+
+/// A guard that cancels its token when it's dropped, created by [`CancellationToken::drop_guard`].
+pub struct DropGuard {
+    token: Option<CancellationToken>,
+}
+
+impl DropGuard {
+    /// Returns the token, without cancelling it.
+    pub fn disarm(mut self) -> CancellationToken {
+        self.token.take().expect("guard is already disarmed")
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        if let Some(token) = &self.token {
+            token.cancel();
+        }
+    }
+}
+
+impl fmt::Debug for DropGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropGuard")
+            .field("token", &self.token)
+            .finish()
+    }
+}
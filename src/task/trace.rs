@@ -0,0 +1,226 @@
+//! Instrumentation of spawned tasks, which feeds the metrics and `tracing`, if the feature is enabled.
+
+use super::{unwind::report, PanicPayload, Task};
+use crate::{
+    metrics::{self, Outcome},
+    poll_timing,
+};
+use std::{
+    cell::Cell,
+    panic::{self, AssertUnwindSafe},
+    task::Poll,
+};
+
+/// The kind of a spawned task.
+#[derive(Clone, Copy)]
+pub(crate) enum Kind {
+    Async,
+    Blocking,
+    #[cfg_attr(not(local_spawn), allow(dead_code))]
+    Local,
+}
+
+impl Kind {
+    #[cfg_attr(not(any(feature = "tracing", feature = "task_dump")), allow(dead_code))]
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Async => "async",
+            Kind::Blocking => "blocking",
+            Kind::Local => "local",
+        }
+    }
+}
+
+/// How a task failed, if the failure was caught before it reached the task's future.
+#[derive(Clone, Copy)]
+enum Caught {
+    Aborted,
+    Panicked,
+}
+
+thread_local! {
+    /// Set if the task that is currently running was aborted or panicked.
+    static CAUGHT: Cell<Option<Caught>> = const { Cell::new(None) };
+}
+
+/// Marks the task that is currently running as aborted, so its completion is
+/// reported as a cancellation.
+pub(crate) fn aborted() {
+    CAUGHT.with(|caught| caught.set(Some(Caught::Aborted)));
+}
+
+/// Marks the task that is currently running as panicked, if the panic was caught,
+/// and reports the panic to the [panic handler](super::on_task_panic).
+pub(crate) fn panicked(payload: &PanicPayload) {
+    CAUGHT.with(|caught| caught.set(Some(Caught::Panicked)));
+    if let Some(task) = super::current() {
+        report(&task, payload);
+    }
+}
+
+/// Records the lifecycle of a single task in the [metrics](crate::metrics), emits its
+/// span and events if the `tracing` feature is enabled, and keeps it in the task
+/// registry if the `task_dump` feature is enabled.
+pub(crate) struct TaskTrace {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    task: Task,
+    state: State,
+}
+
+struct State {
+    kind: Kind,
+    started: bool,
+    finished: bool,
+    #[cfg(feature = "task_dump")]
+    registration: Option<crate::debug::Registration>,
+}
+
+impl TaskTrace {
+    /// Creates the trace of a task, which is about to be spawned.
+    pub(crate) fn new(task: &Task, kind: Kind) -> Self {
+        #[cfg(feature = "tracing")]
+        let span = {
+            let span = tracing::info_span!(
+                "task",
+                task.id = task.id().as_u64(),
+                task.name = tracing::field::Empty,
+                task.kind = kind.as_str(),
+                backend = crate::executor::BACKEND,
+                spawn.location = %task.location(),
+            );
+            if let Some(name) = task.name() {
+                span.record("task.name", name);
+            }
+            tracing::debug!(parent: &span, "task spawned");
+            span
+        };
+        metrics::spawned(matches!(kind, Kind::Blocking));
+        Self {
+            #[cfg(feature = "tracing")]
+            span,
+            task: task.clone(),
+            state: State {
+                kind,
+                started: false,
+                finished: false,
+                #[cfg(feature = "task_dump")]
+                registration: Some(crate::debug::Registration::new(task, kind.as_str())),
+            },
+        }
+    }
+
+    /// Polls the task inside of its span.
+    pub(crate) fn poll<T>(&mut self, poll: impl FnOnce() -> Poll<T>) -> Poll<T> {
+        #[cfg(feature = "tracing")]
+        let _enter = self.span.enter();
+        #[cfg(feature = "tracing")]
+        tracing::trace!("task poll started");
+
+        metrics::polled();
+        let (state, task) = (&mut self.state, &self.task);
+        let (poll, caught) = poll_timing::time(task, || state.catch(task, poll));
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(ready = poll.is_ready(), "task poll ended");
+
+        if poll.is_ready() {
+            self.state.finish(caught);
+        }
+        poll
+    }
+
+    /// Runs a blocking task inside of its span.
+    pub(crate) fn run<T>(mut self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        let _enter = self.span.enter();
+        #[cfg(feature = "tracing")]
+        tracing::trace!("blocking task started");
+
+        self.state.started = true;
+        metrics::blocking_started();
+        let (output, caught) = self.state.catch(&self.task, f);
+
+        self.state.finish(caught);
+        output
+    }
+}
+
+impl State {
+    /// Runs the closure, and returns how it failed, if the failure was caught inside of it.
+    ///
+    /// Records the task as panicked if the closure panics, reports the panic to the
+    /// [panic handler](super::on_task_panic), and resumes the panic.
+    fn catch<T>(&mut self, task: &Task, f: impl FnOnce() -> T) -> (T, Option<Caught>) {
+        #[cfg(feature = "task_dump")]
+        let f = {
+            let registration = self.registration.as_ref();
+            move || match registration {
+                Some(registration) => registration.run(f),
+                None => f(),
+            }
+        };
+
+        let outer = CAUGHT.with(Cell::take);
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        let caught = CAUGHT.with(|caught| caught.replace(outer));
+
+        match result {
+            Ok(output) => (output, caught),
+            Err(payload) => {
+                report(task, &payload);
+                self.finish(Some(Caught::Panicked));
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+
+    /// Records that the task finished.
+    fn finish(&mut self, caught: Option<Caught>) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        #[cfg(feature = "task_dump")]
+        self.registration.take();
+        if self.started {
+            metrics::blocking_finished();
+        }
+
+        let outcome = match caught {
+            Some(Caught::Aborted) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("task cancelled");
+                Outcome::Cancelled
+            }
+            Some(Caught::Panicked) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("task panicked");
+                Outcome::Panicked
+            }
+            None => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("task completed");
+                Outcome::Completed
+            }
+        };
+        metrics::finished(outcome);
+    }
+}
+
+impl Drop for TaskTrace {
+    fn drop(&mut self) {
+        let state = &mut self.state;
+        if state.finished {
+            return;
+        }
+        state.finished = true;
+        if matches!(state.kind, Kind::Blocking) && !state.started {
+            metrics::blocking_dropped();
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, "task cancelled");
+        metrics::finished(Outcome::Cancelled);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{trace, JoinError, Task, TaskFuture};
+use crate::{
+    channel::oneshot,
+    join_handle::{InnerJoinHandle, JoinHandle},
+};
+use once_cell::sync::Lazy;
+use std::{
+    any::Any,
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+
+/// The payload of a panic, as returned by [`std::panic::catch_unwind`].
+pub type PanicPayload = Box<dyn Any + Send + 'static>;
+
+type Handler = Arc<dyn Fn(&Task, &PanicPayload) + Send + Sync>;
+
+/// A handler, which is called whenever a task panics.
+#[derive(Clone)]
+pub(crate) struct PanicHandler(Handler);
+
+impl PanicHandler {
+    pub(crate) fn new(handler: impl Fn(&Task, &PanicPayload) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+}
+
+impl fmt::Debug for PanicHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("PanicHandler")
+    }
+}
+
+static HANDLER: Lazy<RwLock<Option<PanicHandler>>> = Lazy::new(|| RwLock::new(None));
+
+/// Sets the global handler, which is called whenever a task panics, replacing the
+/// previous one.
+///
+/// The handler receives the [`Task`] that panicked, which has its id and name, and the
+/// payload of the panic. It's called by every executor, before the panic is propagated
+/// to the task's [`JoinHandle`](crate::join_handle::JoinHandle), so crash reports look
+/// the same on every backend. Panics that are caught by [`spawn_catch_unwind`](crate::spawn_catch_unwind)
+/// are reported as well.
+///
+/// Executors may have their own handler, like a [`Pool`](crate::Pool) that was built
+/// using [`PoolBuilder::on_task_panic`](crate::pool::PoolBuilder::on_task_panic), in which
+/// case the panics of their tasks are only reported to it. The global handler is called
+/// for the panics of all other tasks.
+///
+/// ```ignore
+/// agnostik::task::on_task_panic(|task, payload| {
+///     let message = payload
+///         .downcast_ref::<&str>()
+///         .copied()
+///         .or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+///     eprintln!("task {} ({:?}) panicked: {:?}", task.id(), task.name(), message);
+/// });
+/// ```
+pub fn on_task_panic(handler: impl Fn(&Task, &PanicPayload) + Send + Sync + 'static) {
+    *HANDLER.write().unwrap() = Some(PanicHandler::new(handler));
+}
+
+/// Calls the panic handler of the task's executor, or the global one, if one is set.
+pub(crate) fn report(task: &Task, payload: &PanicPayload) {
+    let handler = task
+        .panic_handler()
+        .cloned()
+        .or_else(|| HANDLER.read().unwrap().clone());
+    if let Some(PanicHandler(handler)) = handler {
+        handler(task, payload);
+    }
+}
+
+/// A future that catches the panics of the inner future, and returns their payload.
+#[pin_project::pin_project]
+pub(crate) struct CatchUnwind<F> {
+    #[pin]
+    future: F,
+}
+
+impl<F> CatchUnwind<F> {
+    pub(crate) fn new(future: F) -> Self {
+        Self { future }
+    }
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, PanicPayload>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.project().future;
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(payload) => {
+                trace::panicked(&payload);
+                Poll::Ready(Err(payload))
+            }
+        }
+    }
+}
+
+/// A future that catches the panics of a task, which were already reported by its
+/// [`TaskFuture`], so the backend's join handle can tell them apart from a cancellation.
+#[cfg(bastion)]
+#[pin_project::pin_project]
+pub(crate) struct Joinable<F> {
+    #[pin]
+    future: F,
+}
+
+#[cfg(bastion)]
+impl<F> Joinable<F> {
+    pub(crate) fn new(future: F) -> Self {
+        Self { future }
+    }
+}
+
+#[cfg(bastion)]
+impl<F: Future> Future for Joinable<F> {
+    type Output = Result<F::Output, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.project().future;
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(payload) => Poll::Ready(Err(JoinError::panic(payload))),
+        }
+    }
+}
+
+/// Wraps the future of a task, which is polled by a scheduler of agnostik instead of a
+/// backend, and returns the handle that receives its result, or the payload of its panic.
+pub(crate) fn remote<F>(task: Task, future: F) -> (Remote<F>, JoinHandle<F::Output>)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let id = task.id();
+    let (tx, rx) = oneshot::channel();
+    let future = Remote {
+        future: TaskFuture::new(task, future),
+        tx: Some(tx),
+    };
+    let handle = JoinHandle::new(
+        id,
+        InnerJoinHandle::Global(Box::pin(async move {
+            rx.await.unwrap_or_else(|_| Err(JoinError::cancelled()))
+        })),
+    );
+    (future, handle)
+}
+
+/// A future that sends the result of a task to its join handle, returned by [`remote`].
+#[pin_project::pin_project]
+pub(crate) struct Remote<F: Future> {
+    #[pin]
+    future: TaskFuture<F>,
+    tx: Option<oneshot::Sender<Result<F::Output, JoinError>>>,
+}
+
+impl<F: Future> Future for Remote<F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.project();
+        let future = this.future;
+        let result = match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => return Poll::Pending,
+            Ok(Poll::Ready(output)) => Ok(output),
+            Err(payload) => Err(JoinError::panic(payload)),
+        };
+        if let Some(tx) = this.tx.take() {
+            let _ = tx.send(result);
+        }
+        Poll::Ready(())
+    }
+}
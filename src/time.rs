@@ -0,0 +1,232 @@
+//! Runtime independent timers.
+//!
+//! The timers don't depend on any runtime, so they work with every executor supported
+//! by agnostik. All timers are driven by a single background thread, which is started
+//! when the first timer is created.
+//!
+//! For tests, the clock can be [paused](pause) and [advanced](advance) manually, so code
+//! that sleeps for a long time can be tested without waiting. The [`next_deadline`] tells
+//! how far the clock has to be advanced to wake the next timer.
+//!
+//! ```ignore
+//! use std::time::Duration;
+//!
+//! agnostik::block_on(async {
+//!     agnostik::time::sleep(Duration::from_millis(10)).await;
+//! });
+//! ```
+
+use once_cell::sync::Lazy;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Condvar, Mutex as StdMutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+static TIMER: Lazy<&'static Timer> = Lazy::new(|| {
+    let timer: &'static Timer = Box::leak(Box::new(Timer {
+        state: StdMutex::new(State {
+            deadlines: BinaryHeap::new(),
+            wakers: HashMap::new(),
+            next_id: 0,
+            offset: Duration::from_secs(0),
+            paused: None,
+        }),
+        changed: Condvar::new(),
+    }));
+    thread::Builder::new()
+        .name("agnostik-timer".into())
+        .spawn(move || timer.run())
+        .expect("failed to spawn the timer thread");
+    timer
+});
+
+struct Timer {
+    state: StdMutex<State>,
+    /// Notified whenever a deadline was added, that is earlier than all others,
+    /// and whenever the clock is paused, advanced or resumed.
+    changed: Condvar,
+}
+
+struct State {
+    /// The deadlines of all registered timers, which may contain timers that were dropped.
+    deadlines: BinaryHeap<Reverse<(Instant, u64)>>,
+    wakers: HashMap<u64, Waker>,
+    next_id: u64,
+    /// How far the clock is ahead of the real time, because it was advanced.
+    offset: Duration,
+    /// The current time, while the clock is paused.
+    paused: Option<Instant>,
+}
+
+impl State {
+    fn now(&self) -> Instant {
+        self.paused.unwrap_or_else(|| Instant::now() + self.offset)
+    }
+}
+
+impl Timer {
+    /// Wakes the timers whose deadline passed, and waits for the next deadline.
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let now = state.now();
+            let mut expired = Vec::new();
+            while let Some(Reverse((deadline, id))) = state.deadlines.peek().copied() {
+                if deadline > now {
+                    break;
+                }
+                state.deadlines.pop();
+                expired.extend(state.wakers.remove(&id));
+            }
+
+            if !expired.is_empty() {
+                drop(state);
+                expired.into_iter().for_each(Waker::wake);
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            state = match state.deadlines.peek() {
+                Some(Reverse((deadline, _))) if state.paused.is_none() => {
+                    let timeout = deadline.saturating_duration_since(now);
+                    self.changed.wait_timeout(state, timeout).unwrap().0
+                }
+                _ => self.changed.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+/// Returns the current time of the timers, which stands still while the clock is [paused](pause).
+pub fn now() -> Instant {
+    TIMER.state.lock().unwrap().now()
+}
+
+/// Pauses the clock, so time only passes when it's [advanced](advance).
+///
+/// The clock is global, so pausing it affects all timers in the process.
+/// Pausing a paused clock does nothing.
+pub fn pause() {
+    let mut state = TIMER.state.lock().unwrap();
+    if state.paused.is_none() {
+        state.paused = Some(state.now());
+        TIMER.changed.notify_one();
+    }
+}
+
+/// Advances the paused clock by the duration, and wakes the timers whose deadline passed.
+///
+/// # Panics
+///
+/// Panics if the clock isn't paused.
+pub fn advance(duration: Duration) {
+    let mut state = TIMER.state.lock().unwrap();
+    let now = state.paused.expect("the clock isn't paused");
+    state.paused = Some(now + duration);
+    TIMER.changed.notify_one();
+}
+
+/// Returns the deadline of the earliest timer that is still waiting, if there is one.
+///
+/// Together with [`advance`], this lets a test move the paused clock straight to the
+/// next timer, once the tasks it runs are waiting for one.
+pub fn next_deadline() -> Option<Instant> {
+    let state = TIMER.state.lock().unwrap();
+    state
+        .deadlines
+        .iter()
+        .filter(|Reverse((_, id))| state.wakers.contains_key(id))
+        .map(|Reverse((deadline, _))| *deadline)
+        .min()
+}
+
+/// Resumes the paused clock, which continues from the time it was paused at, so the time
+/// it was advanced by isn't lost and [`now`] never goes backwards.
+pub fn resume() {
+    let mut state = TIMER.state.lock().unwrap();
+    if let Some(paused) = state.paused.take() {
+        state.offset = paused.saturating_duration_since(Instant::now());
+        TIMER.changed.notify_one();
+    }
+}
+
+/// Waits until the duration has elapsed.
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(now() + duration)
+}
+
+/// Waits until the deadline has passed.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep { deadline, id: None }
+}
+
+/// Future returned by [`sleep`] and [`sleep_until`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    deadline: Instant,
+    /// The id of the timer, once it was registered.
+    id: Option<u64>,
+}
+
+impl Sleep {
+    /// Returns the deadline of the timer.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = TIMER.state.lock().unwrap();
+        if state.now() >= self.deadline {
+            if let Some(id) = self.id.take() {
+                state.wakers.remove(&id);
+            }
+            return Poll::Ready(());
+        }
+
+        if let Some(id) = self.id {
+            state.wakers.insert(id, cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        let earliest = state
+            .deadlines
+            .peek()
+            .map_or(true, |Reverse((deadline, _))| self.deadline < *deadline);
+        state.deadlines.push(Reverse((self.deadline, id)));
+        state.wakers.insert(id, cx.waker().clone());
+        self.id = Some(id);
+        if earliest {
+            TIMER.changed.notify_one();
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            TIMER.state.lock().unwrap().wakers.remove(&id);
+        }
+    }
+}
+
+impl fmt::Debug for Sleep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sleep")
+            .field("deadline", &self.deadline)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,49 @@
+#![cfg(all(
+    target_os = "linux",
+    any(
+        feature = "runtime_bastion",
+        feature = "runtime_asyncstd",
+        feature = "runtime_tokio",
+        feature = "runtime_tokio1",
+        feature = "runtime_smol"
+    )
+))]
+
+use agnostik::{affinity, AgnostikExecutor, Pool};
+
+#[test]
+fn test_pool_pins_its_threads() {
+    let all = affinity::current_thread_affinity().unwrap();
+    let cpu = all[0];
+
+    let pool = Pool::builder()
+        .threads(2)
+        .pin_threads(vec![cpu])
+        .build()
+        .unwrap();
+    let pinned = Pool::builder()
+        .threads(1)
+        .pin_threads(vec![cpu])
+        .pin_blocking_tasks(true)
+        .build()
+        .unwrap();
+
+    let tasks = (0..4)
+        .map(|_| pool.spawn(async { affinity::current_thread_affinity().unwrap() }))
+        .collect::<Vec<_>>();
+    let blocking = pool.spawn_blocking(|| affinity::current_thread_affinity().unwrap());
+    let pinned_blocking = pinned.spawn_blocking(|| affinity::current_thread_affinity().unwrap());
+    let unpinned = all.clone();
+    agnostik::block_on(async move {
+        for task in tasks {
+            assert_eq!(task.await, [cpu]);
+        }
+        assert_eq!(blocking.await, unpinned);
+        assert_eq!(pinned_blocking.await, [cpu]);
+    });
+
+    // the test thread isn't pinned
+    assert_eq!(affinity::current_thread_affinity().unwrap(), all);
+    assert!(Pool::builder().pin_threads(vec![]).build().is_err());
+    assert!(Pool::builder().pin_threads(vec![4096]).build().is_err());
+}
@@ -0,0 +1,55 @@
+#![cfg(all(
+    target_os = "linux",
+    any(feature = "runtime_tokio", feature = "runtime_tokio1")
+))]
+
+use agnostik::affinity;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Keeps the tests from replacing the runtime of the global executor at the same time.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn affinities() -> (Vec<usize>, Vec<usize>) {
+    agnostik::block_on(async {
+        let task = agnostik::spawn(async { affinity::current_thread_affinity().unwrap() });
+        let blocking = agnostik::spawn_blocking(|| affinity::current_thread_affinity().unwrap());
+        (task.await, blocking.await)
+    })
+}
+
+#[test]
+fn test_pin_global_worker_threads() {
+    let _lock = lock();
+    let all = affinity::current_thread_affinity().unwrap();
+    let cpu = all[0];
+
+    affinity::pin_global_threads(vec![cpu], false).unwrap();
+    assert_eq!(affinities(), (vec![cpu], all.clone()));
+
+    // the test thread isn't pinned
+    assert_eq!(affinity::current_thread_affinity().unwrap(), all);
+}
+
+#[test]
+fn test_pin_global_blocking_threads() {
+    let _lock = lock();
+    let all = affinity::current_thread_affinity().unwrap();
+    let cpu = all[0];
+
+    affinity::pin_global_threads(vec![cpu], true).unwrap();
+    assert_eq!(affinities(), (vec![cpu], vec![cpu]));
+
+    // the test thread isn't pinned
+    assert_eq!(affinity::current_thread_affinity().unwrap(), all);
+}
+
+#[test]
+fn test_pin_global_threads_to_invalid_cpus() {
+    let _lock = lock();
+    assert!(affinity::pin_global_threads(vec![], false).is_err());
+    assert!(affinity::pin_global_threads(vec![4096], false).is_err());
+}
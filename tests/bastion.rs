@@ -15,3 +15,18 @@ fn test_bastion() {
 
     agnostik.block_on(handle);
 }
+
+#[cfg(feature = "runtime_bastion")]
+#[test]
+fn test_bastion_panic_is_not_cancellation() {
+    let agnostik = Agnostik::bastion();
+
+    let handle = agnostik.spawn(async { panic!("bastion task panicked") });
+
+    let err = agnostik.block_on(handle.try_join()).unwrap_err();
+    assert!(err.is_panic());
+    assert_eq!(
+        err.into_panic().downcast_ref::<&str>(),
+        Some(&"bastion task panicked")
+    );
+}
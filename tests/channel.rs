@@ -0,0 +1,212 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::channel::{broadcast, mpsc, oneshot, watch};
+use futures::{SinkExt, StreamExt};
+
+#[test]
+fn test_mpsc_bounded() {
+    agnostik::block_on(async {
+        let (tx, mut rx) = mpsc::channel(2);
+
+        let handles = (0..4)
+            .map(|i| {
+                let tx = tx.clone();
+                agnostik::spawn(async move {
+                    for j in 0..25 {
+                        tx.send(i * 25 + j).await.unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(tx);
+
+        let mut values = Vec::new();
+        while let Some(value) = rx.recv().await {
+            values.push(value);
+        }
+        futures::future::join_all(handles).await;
+
+        values.sort_unstable();
+        assert_eq!(values, (0..100).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn test_mpsc_try_send() {
+    let (tx, mut rx) = mpsc::channel(1);
+    tx.try_send(1).unwrap();
+    assert_eq!(tx.try_send(2), Err(mpsc::TrySendError::Full(2)));
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Empty));
+
+    rx.close();
+    assert!(tx.is_closed());
+    assert_eq!(tx.try_send(3), Err(mpsc::TrySendError::Closed(3)));
+    assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+}
+
+#[test]
+fn test_mpsc_blocking() {
+    agnostik::block_on(async {
+        let (tx, mut rx) = mpsc::channel(1);
+        let (result_tx, mut result_rx) = mpsc::unbounded_channel();
+
+        let producer = agnostik::spawn_blocking(move || {
+            for i in 0..10 {
+                tx.send_blocking(i).unwrap();
+            }
+        });
+        let consumer = agnostik::spawn_blocking(move || {
+            let mut sum = 0;
+            while let Some(value) = rx.recv_blocking() {
+                sum += value;
+            }
+            result_tx.send(sum).unwrap();
+        });
+
+        assert_eq!(result_rx.recv().await, Some(45));
+        futures::join!(producer, consumer);
+    });
+}
+
+#[test]
+fn test_mpsc_sink_and_stream() {
+    agnostik::block_on(async {
+        let (mut tx, rx) = mpsc::channel(4);
+        let handle = agnostik::spawn(async move {
+            for i in 0..10 {
+                SinkExt::send(&mut tx, i).await.unwrap();
+            }
+        });
+
+        let values = rx.collect::<Vec<_>>().await;
+        handle.await;
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn test_oneshot() {
+    agnostik::block_on(async {
+        let (tx, rx) = oneshot::channel();
+        let handle = agnostik::spawn(async move {
+            tx.send(42).unwrap();
+        });
+        assert_eq!(rx.await, Ok(42));
+        handle.await;
+
+        let (tx, rx) = oneshot::channel::<()>();
+        drop(tx);
+        assert!(rx.await.is_err());
+
+        let (tx, rx) = oneshot::channel();
+        drop(rx);
+        assert_eq!(tx.send(1), Err(1));
+    });
+}
+
+#[test]
+fn test_oneshot_blocking() {
+    agnostik::block_on(async {
+        let (tx, rx) = oneshot::channel();
+        let handle = agnostik::spawn_blocking(move || rx.recv_blocking());
+        tx.send("hello").unwrap();
+        assert_eq!(handle.await, Ok("hello"));
+    });
+}
+
+#[test]
+fn test_broadcast() {
+    agnostik::block_on(async {
+        let (tx, mut rx1) = broadcast::channel(16);
+        let mut rx2 = tx.subscribe();
+
+        assert_eq!(tx.send(1), Ok(2));
+        assert_eq!(tx.send(2), Ok(2));
+        drop(tx);
+
+        assert_eq!(rx1.recv().await, Ok(1));
+        assert_eq!(rx1.recv().await, Ok(2));
+        assert_eq!(rx1.recv().await, Err(broadcast::RecvError::Closed));
+
+        let values = rx2.by_ref().collect::<Vec<_>>().await;
+        assert_eq!(values, vec![Ok(1), Ok(2)]);
+    });
+}
+
+#[test]
+fn test_broadcast_lagged() {
+    let (tx, mut rx) = broadcast::channel(2);
+    for i in 0..5 {
+        tx.send(i).unwrap();
+    }
+
+    assert_eq!(rx.try_recv(), Err(broadcast::TryRecvError::Lagged(3)));
+    assert_eq!(rx.try_recv(), Ok(3));
+    assert_eq!(rx.try_recv(), Ok(4));
+    assert_eq!(rx.try_recv(), Err(broadcast::TryRecvError::Empty));
+}
+
+#[test]
+fn test_broadcast_wakes_receivers() {
+    agnostik::block_on(async {
+        let (tx, rx) = broadcast::channel(4);
+        let handles = (0..3)
+            .map(|_| {
+                let mut rx = rx.resubscribe();
+                agnostik::spawn(async move { rx.recv().await })
+            })
+            .collect::<Vec<_>>();
+        drop(rx);
+
+        assert_eq!(tx.send(String::from("ping")), Ok(3));
+        for result in futures::future::join_all(handles).await {
+            assert_eq!(result.as_deref(), Ok("ping"));
+        }
+    });
+}
+
+#[test]
+fn test_watch() {
+    agnostik::block_on(async {
+        let (tx, mut rx) = watch::channel(0);
+        assert_eq!(*rx.borrow(), 0);
+        assert_eq!(rx.has_changed(), Ok(false));
+
+        let handle = agnostik::spawn(async move {
+            let mut seen = Vec::new();
+            while rx.changed().await.is_ok() {
+                seen.push(*rx.borrow_and_update());
+            }
+            seen
+        });
+
+        for i in 1..=3 {
+            tx.send_replace(i);
+        }
+        drop(tx);
+
+        let seen = handle.await;
+        assert_eq!(seen.last(), Some(&3));
+    });
+}
+
+#[test]
+fn test_watch_blocking() {
+    agnostik::block_on(async {
+        let (tx, mut rx) = watch::channel("init");
+        let handle = agnostik::spawn_blocking(move || {
+            rx.changed_blocking().unwrap();
+            *rx.borrow()
+        });
+
+        tx.send("updated").unwrap();
+        assert_eq!(handle.await, "updated");
+    });
+}
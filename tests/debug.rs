@@ -0,0 +1,89 @@
+#![cfg(all(
+    feature = "task_dump",
+    any(
+        feature = "runtime_bastion",
+        feature = "runtime_asyncstd",
+        feature = "runtime_tokio",
+        feature = "runtime_tokio1",
+        feature = "runtime_smol"
+    )
+))]
+
+use agnostik::{channel::oneshot, debug};
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+/// Keeps the tasks of the other tests out of the dumps, since the registry is global.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dump_on_signal() {
+    debug::dump_on_signal().unwrap();
+    debug::dump_on_signal().unwrap();
+}
+
+#[test]
+fn test_dump_waiting_task() {
+    let _lock = lock();
+    let (tx, rx) = oneshot::channel::<()>();
+    agnostik::block_on(async move {
+        let (polled_tx, polled_rx) = oneshot::channel::<()>();
+        let waiting = agnostik::task::Builder::new()
+            .name("waiting")
+            .spawn(async move {
+                polled_tx.send(()).unwrap();
+                rx.await.unwrap()
+            });
+        let id = waiting.id();
+        polled_rx.await.unwrap();
+
+        let dump = debug::dump_tasks();
+        assert_eq!(dump.tasks().len(), 1);
+        assert!(dump.to_string().starts_with("1 tasks alive\n"));
+
+        let task = &dump.tasks()[0];
+        assert_eq!(task.task().id(), id);
+        assert_eq!(task.task().name(), Some("waiting"));
+        assert_eq!(task.kind(), "async");
+        assert!(task.polls() >= 1);
+        assert!(task.since_last_poll().unwrap() <= task.age());
+        assert!(task
+            .to_string()
+            .contains("'waiting' (async) spawned at tests/debug.rs"));
+
+        tx.send(()).unwrap();
+        waiting.await;
+    });
+
+    assert!(debug::dump_tasks().tasks().is_empty());
+}
+
+#[test]
+fn test_dump_running_blocking_task() {
+    let _lock = lock();
+    agnostik::block_on(async {
+        let (started_tx, started_rx) = oneshot::channel::<()>();
+        let blocking = agnostik::spawn_blocking(move || {
+            started_tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+        });
+        started_rx.await.unwrap();
+
+        let dump = debug::dump_tasks();
+        assert_eq!(dump.tasks().len(), 1);
+        let running = &dump.tasks()[0];
+        assert_eq!(running.kind(), "blocking");
+        assert!(running.is_running());
+
+        blocking.await;
+    });
+
+    assert!(debug::dump_tasks().tasks().is_empty());
+}
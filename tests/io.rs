@@ -0,0 +1,100 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::io;
+use futures::{io::AsyncWriteExt, StreamExt};
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Set when the test binary runs as a child process, whose stdin is piped.
+const CHILD: &str = "AGNOSTIK_STDIN_CHILD";
+/// Marks the output of the child that is checked by the parent, which may follow the
+/// output of the test harness on the same line.
+const MARK: &str = "line: ";
+
+#[test]
+fn test_stdout() {
+    agnostik::block_on(async {
+        let mut stdout = io::stdout();
+        stdout.write_all(b"hello from stdout\n").await.unwrap();
+        stdout.flush().await.unwrap();
+    });
+}
+
+#[test]
+fn test_stderr() {
+    agnostik::block_on(async {
+        let mut stderr = io::stderr();
+        for i in 0..3 {
+            let line = format!("hello from stderr: {}\n", i);
+            stderr.write_all(line.as_bytes()).await.unwrap();
+        }
+        stderr.close().await.unwrap();
+    });
+}
+
+// only does something when it's run by `test_stdin_lines`
+#[test]
+fn stdin_child() {
+    if env::var_os(CHILD).is_none() {
+        return;
+    }
+    agnostik::block_on(async {
+        let mut stdin = io::stdin();
+        let mut line = String::from("> ");
+        let read = stdin.read_line(&mut line).await.unwrap();
+        println!("{}{:?} {}", MARK, line, read);
+
+        let mut lines = stdin.lines();
+        while let Some(line) = lines.next().await {
+            println!("{}{:?}", MARK, line.unwrap());
+        }
+
+        let mut line = String::new();
+        let read = lines.into_inner().read_line(&mut line).await.unwrap();
+        println!("{}{:?} {}", MARK, line, read);
+    });
+}
+
+#[test]
+fn test_stdin_lines() {
+    let mut child = Command::new(env::current_exe().unwrap())
+        .args(["stdin_child", "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD, "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(b"first\r\nsecond\n\nthird\r\nlast")
+        .unwrap();
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let output = String::from_utf8(output.stdout).unwrap();
+    let lines = output
+        .lines()
+        .filter_map(|line| line.find(MARK).map(|idx| &line[idx + MARK.len()..]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            r#""> first\r\n" 7"#,
+            r#""second""#,
+            r#""""#,
+            r#""third""#,
+            r#""last""#,
+            r#""" 0"#,
+        ]
+    );
+}
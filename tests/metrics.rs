@@ -0,0 +1,108 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::Metrics;
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Keeps the tasks of the other tests from being counted, since the metrics are global.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Waits until all tasks finished, since a task may report its result before it's done.
+fn wait_until_idle() -> Metrics {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    loop {
+        let metrics = agnostik::metrics();
+        if metrics.alive() == 0 || Instant::now() >= deadline {
+            return metrics;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Runs the future on the global executor, and returns the metrics before and after it.
+fn measure<F>(future: F) -> (Metrics, Metrics)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let _lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let before = wait_until_idle();
+    agnostik::block_on(future);
+    (before, wait_until_idle())
+}
+
+#[test]
+fn test_completed_task() {
+    let (before, after) = measure(async {
+        assert_eq!(agnostik::spawn(async { 1 }).await, 1);
+    });
+    assert_eq!(after.spawned() - before.spawned(), 1);
+    assert_eq!(after.completed() - before.completed(), 1);
+    assert_eq!(after.panicked(), before.panicked());
+    assert_eq!(after.cancelled(), before.cancelled());
+    assert_eq!(after.alive(), 0);
+}
+
+#[test]
+fn test_panicked_task() {
+    let (before, after) = measure(async {
+        let panicked = agnostik::spawn(async { panic!("boom") });
+        let result = futures::FutureExt::catch_unwind(AssertUnwindSafe(panicked)).await;
+        assert!(result.is_err());
+    });
+    assert_eq!(after.spawned() - before.spawned(), 1);
+    assert_eq!(after.panicked() - before.panicked(), 1);
+    assert_eq!(after.completed(), before.completed());
+    assert_eq!(after.alive(), 0);
+}
+
+#[test]
+fn test_cancelled_task() {
+    let (before, after) = measure(async {
+        let mut group = agnostik::task::TaskGroup::new();
+        group.spawn(futures::future::pending::<()>());
+        group.abort_all();
+        assert!(group.join_next().await.unwrap().unwrap_err().is_cancelled());
+    });
+    assert_eq!(after.spawned() - before.spawned(), 1);
+    assert_eq!(after.cancelled() - before.cancelled(), 1);
+    assert_eq!(after.completed(), before.completed());
+    assert_eq!(after.alive(), 0);
+}
+
+#[test]
+fn test_blocking_task() {
+    let (before, after) = measure(async {
+        assert_eq!(agnostik::spawn_blocking(|| 2).await, 2);
+    });
+    assert_eq!(after.spawned() - before.spawned(), 1);
+    assert_eq!(after.completed() - before.completed(), 1);
+    assert!(after.blocking_threads() >= 1);
+    assert_eq!(after.blocking_running(), 0);
+    assert_eq!(after.blocking_queue_depth(), 0);
+}
+
+#[test]
+fn test_worker_polls() {
+    let polls = |metrics: &Metrics| {
+        let workers = metrics.workers().iter();
+        workers.map(|worker| worker.polls()).sum::<u64>()
+    };
+    let (before, after) = measure(async {
+        for i in 0..3 {
+            assert_eq!(agnostik::spawn(async move { i }).await, i);
+        }
+    });
+    assert!(polls(&after) - polls(&before) >= 3);
+}
@@ -0,0 +1,114 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::net::{lookup_host, AsyncToSocketAddrs, TcpListener, TcpStream};
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+#[test]
+fn test_lookup_localhost() {
+    let addrs = agnostik::block_on(async { lookup_host("localhost:8080").await })
+        .unwrap()
+        .collect::<Vec<_>>();
+
+    assert!(!addrs.is_empty());
+    for addr in addrs {
+        assert!(addr.ip().is_loopback());
+        assert_eq!(addr.port(), 8080);
+    }
+}
+
+#[test]
+fn test_lookup_host_and_port() {
+    let addrs = agnostik::block_on(async { lookup_host(("localhost", 80)).await })
+        .unwrap()
+        .collect::<Vec<_>>();
+
+    let loopback = [
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 80),
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 80),
+    ];
+    assert!(!addrs.is_empty());
+    assert!(
+        addrs.iter().all(|addr| loopback.contains(addr)),
+        "{:?}",
+        addrs
+    );
+}
+
+#[test]
+fn test_lookup_ip_address() {
+    let addrs = agnostik::block_on(async {
+        let parsed = lookup_host("127.0.0.1:1234")
+            .await
+            .unwrap()
+            .collect::<Vec<_>>();
+        let tuple = (Ipv4Addr::LOCALHOST, 1234)
+            .to_socket_addrs()
+            .await
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(parsed, tuple);
+        parsed
+    });
+
+    let expected = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1234);
+    assert_eq!(addrs, vec![expected]);
+}
+
+#[test]
+fn test_lookup_invalid_port() {
+    let res = agnostik::block_on(async { lookup_host("localhost").await });
+    assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    let res = agnostik::block_on(async { lookup_host("localhost:http").await });
+    assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_tcp_connect_and_accept() {
+    agnostik::block_on(async {
+        let listener = TcpListener::bind("localhost:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(addr.ip().is_loopback());
+
+        let server = agnostik::spawn(async move {
+            let (mut stream, peer) = listener.accept().await.unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), peer);
+            let mut buf = [0; 4];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(b"pong").await.unwrap();
+            stream.flush().await.unwrap();
+            buf
+        });
+
+        let mut stream = TcpStream::connect(("localhost", addr.port()))
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), addr);
+        stream.write_all(b"ping").await.unwrap();
+        stream.flush().await.unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+        assert_eq!(&server.await, b"ping");
+    });
+}
+
+#[test]
+fn test_tcp_connect_refused() {
+    let res = agnostik::block_on(async {
+        // the port is free once the listener is dropped
+        let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+        TcpStream::connect(("127.0.0.1", port)).await
+    });
+    assert_eq!(
+        res.unwrap_err().kind(),
+        std::io::ErrorKind::ConnectionRefused
+    );
+}
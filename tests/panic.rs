@@ -0,0 +1,145 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{
+    task::{self, Id, PanicPayload},
+    AgnostikExecutor, Pool,
+};
+use futures::FutureExt;
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+fn message(payload: &PanicPayload) -> Option<&str> {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
+
+type Reports = Arc<Mutex<Vec<(Id, Option<String>, String)>>>;
+
+/// Keeps the tests from replacing the global panic handler while another one runs.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Locks the other tests out, and sets a global panic handler that records the panics.
+fn record_panics() -> (MutexGuard<'static, ()>, Reports) {
+    let lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let reports = Reports::default();
+    task::on_task_panic({
+        let reports = reports.clone();
+        move |task, payload| {
+            reports.lock().unwrap().push((
+                task.id(),
+                task.name().map(String::from),
+                message(payload).unwrap_or_default().to_string(),
+            ));
+        }
+    });
+    (lock, reports)
+}
+
+fn take(reports: &Reports) -> Vec<(Id, Option<String>, String)> {
+    std::mem::take(&mut *reports.lock().unwrap())
+}
+
+#[test]
+fn test_named_task_panic_is_reported() {
+    let (_lock, reports) = record_panics();
+    agnostik::block_on(async move {
+        let handle = task::Builder::new()
+            .name("worker")
+            .spawn(async { panic!("named") });
+        let id = handle.id();
+        assert!(handle.try_join().await.unwrap_err().is_panic());
+        assert_eq!(
+            take(&reports),
+            vec![(id, Some("worker".into()), "named".into())]
+        );
+    });
+}
+
+#[test]
+fn test_spawn_catch_unwind() {
+    let (_lock, reports) = record_panics();
+    agnostik::block_on(async move {
+        let handle = agnostik::spawn_catch_unwind(async { panic!("caught") });
+        let id = handle.id();
+        assert_eq!(message(&handle.await.unwrap_err()), Some("caught"));
+        assert_eq!(take(&reports), vec![(id, None, "caught".into())]);
+
+        let handle = agnostik::spawn_catch_unwind(async { 42 });
+        assert_eq!(handle.await.ok(), Some(42));
+        assert!(take(&reports).is_empty());
+    });
+}
+
+#[test]
+fn test_executor_spawn_catch_unwind() {
+    let (_lock, reports) = record_panics();
+    // tasks spawned on the executor directly behave the same on every backend
+    agnostik::block_on(async move {
+        let handle = agnostik::executor().spawn_catch_unwind(async { panic!("direct") });
+        let id = handle.id();
+        assert_eq!(message(&handle.await.unwrap_err()), Some("direct"));
+        assert_eq!(take(&reports), vec![(id, None, "direct".into())]);
+    });
+}
+
+#[test]
+fn test_propagated_panic_is_reported() {
+    let (_lock, reports) = record_panics();
+    // async-std and smol propagate the panic instead of returning a `JoinError`
+    agnostik::block_on(async move {
+        let handle = agnostik::executor().spawn(async { panic!("propagated") });
+        let id = handle.id();
+        let _ = AssertUnwindSafe(handle.try_join()).catch_unwind().await;
+        assert_eq!(take(&reports), vec![(id, None, "propagated".into())]);
+    });
+}
+
+#[test]
+fn test_blocking_panic_is_reported() {
+    let (_lock, reports) = record_panics();
+    agnostik::block_on(async move {
+        let handle = agnostik::spawn_blocking(|| panic!("blocking"));
+        let id = handle.id();
+        assert!(handle.try_join().await.unwrap_err().is_panic());
+        assert_eq!(take(&reports), vec![(id, None, "blocking".into())]);
+    });
+}
+
+#[test]
+fn test_pool_panic_handler() {
+    let (_lock, reports) = record_panics();
+    // the panics of a pool with its own handler aren't reported to the global one
+    let pool_reports = Arc::new(Mutex::new(Vec::<(Id, String)>::new()));
+    let pool = Pool::builder()
+        .threads(1)
+        .on_task_panic({
+            let pool_reports = pool_reports.clone();
+            move |task, payload| {
+                let message = message(payload).unwrap_or_default().to_string();
+                pool_reports.lock().unwrap().push((task.id(), message));
+            }
+        })
+        .build()
+        .unwrap();
+    let handle = pool.spawn_catch_unwind(async { panic!("pool") });
+    let id = handle.id();
+    assert_eq!(message(&pool.block_on(handle).unwrap_err()), Some("pool"));
+    let handle = pool.spawn_blocking(|| panic!("pool blocking"));
+    let blocking_id = handle.id();
+    let _ = pool.block_on(AssertUnwindSafe(handle.try_join()).catch_unwind());
+    assert_eq!(
+        *pool_reports.lock().unwrap(),
+        vec![(id, "pool".into()), (blocking_id, "pool blocking".into())]
+    );
+    assert!(take(&reports).is_empty());
+}
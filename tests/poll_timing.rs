@@ -0,0 +1,109 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{PollHistogram, PollTiming, SlowPoll};
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::Duration,
+};
+
+/// Keeps the tests from replacing the configuration, which is global, while another one runs.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs a task with the given name, which blocks its worker for 50ms, and one that
+/// finishes right away.
+fn run_sleepy_and_quick(sleepy: &'static str, quick: &'static str) {
+    agnostik::block_on(async move {
+        let builder = agnostik::task::Builder::new();
+        builder
+            .clone()
+            .name(sleepy)
+            .spawn(async { thread::sleep(Duration::from_millis(50)) })
+            .await;
+        builder.name(quick).spawn(async {}).await;
+    });
+}
+
+fn histogram(name: &str) -> Option<PollHistogram> {
+    let metrics = agnostik::metrics();
+    let mut histograms = metrics.poll_histograms().iter();
+    histograms
+        .find(|histogram| histogram.name() == Some(name))
+        .cloned()
+}
+
+#[test]
+fn test_slow_poll_is_reported() {
+    let _lock = lock();
+    let slow_polls = Arc::new(Mutex::new(Vec::<SlowPoll>::new()));
+    let reported = slow_polls.clone();
+    PollTiming::new()
+        .slow_poll_threshold(Duration::from_millis(20))
+        .on_slow_poll(move |poll| reported.lock().unwrap().push(poll.clone()))
+        .enable();
+
+    run_sleepy_and_quick("sleepy", "quick");
+    PollTiming::disable();
+
+    let slow_polls = slow_polls.lock().unwrap();
+    assert_eq!(slow_polls.len(), 1);
+    assert_eq!(slow_polls[0].task().name(), Some("sleepy"));
+    assert!(slow_polls[0].duration() >= Duration::from_millis(50));
+    assert!(slow_polls[0]
+        .task()
+        .location()
+        .to_string()
+        .contains("tests/poll_timing.rs"));
+    assert!(slow_polls[0]
+        .to_string()
+        .contains("task 'sleepy' spawned at"));
+}
+
+#[test]
+fn test_poll_histograms() {
+    let _lock = lock();
+    PollTiming::new()
+        .slow_poll_threshold(Duration::from_millis(20))
+        .enable();
+
+    run_sleepy_and_quick("napping", "instant");
+    PollTiming::disable();
+
+    let instant = histogram("instant").unwrap();
+    assert_eq!(instant.slow_polls(), 0);
+
+    let napping = histogram("napping").unwrap();
+    assert_eq!(napping.count(), 1);
+    assert_eq!(napping.slow_polls(), 1);
+    assert!(napping.max() >= Duration::from_millis(50));
+    let bucket = napping.buckets().find(|(_, count)| *count > 0).unwrap();
+    assert_eq!(bucket.0, Duration::from_millis(100));
+}
+
+#[test]
+fn test_disable_keeps_histograms() {
+    let _lock = lock();
+    PollTiming::new().enable();
+    agnostik::block_on(async {
+        let builder = agnostik::task::Builder::new().name("disabled");
+        builder.spawn(async {}).await;
+    });
+    let recorded = histogram("disabled").unwrap();
+
+    PollTiming::disable();
+    agnostik::block_on(async {
+        let builder = agnostik::task::Builder::new().name("disabled");
+        builder.spawn(async {}).await;
+    });
+    assert_eq!(histogram("disabled"), Some(recorded));
+}
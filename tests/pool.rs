@@ -0,0 +1,65 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{
+    channel::oneshot::{self, TryRecvError},
+    AgnostikExecutor, Pool,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+fn thread_name() -> Option<String> {
+    thread::current().name().map(String::from)
+}
+
+#[test]
+fn test_pool_runs_tasks_on_its_threads() {
+    let cpu = Pool::builder().threads(2).name("cpu").build().unwrap();
+    let io = Pool::builder().threads(1).name("io").build().unwrap();
+    assert_eq!((cpu.name(), cpu.threads()), ("cpu", 2));
+
+    let on_cpu = cpu.spawn(async { thread_name() });
+    let on_io = io.spawn(async { thread_name() });
+    let blocking = cpu.spawn_blocking(|| 7);
+    let (on_cpu, on_io, blocking) = agnostik::block_on(async move {
+        let global = agnostik::spawn(async { 42 });
+        assert_eq!(global.await, 42);
+        (on_cpu.await, on_io.await, blocking.await)
+    });
+    assert_eq!(on_cpu.as_deref(), Some("cpu"));
+    assert_eq!(on_io.as_deref(), Some("io"));
+    assert_eq!(blocking, 7);
+
+    assert_eq!(cpu.block_on(async { 1 + 1 }), 2);
+    let nested = cpu.block_on(async { agnostik::spawn(async { 3 }).await });
+    assert_eq!(nested, 3);
+}
+
+#[test]
+fn test_pool_shuts_down_on_drop() {
+    let pool = Pool::builder().threads(1).build().unwrap();
+    let (_never_tx, never_rx) = oneshot::channel::<()>();
+    let (dropped_tx, mut dropped_rx) = oneshot::channel::<()>();
+    let (started_tx, started_rx) = oneshot::channel::<()>();
+    let _handle = pool.spawn(async move {
+        let _guard = dropped_tx;
+        started_tx.send(()).unwrap();
+        never_rx.await.ok();
+    });
+    started_rx.recv_blocking().unwrap();
+    drop(pool);
+
+    // the pending task is dropped, which drops the sender
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while dropped_rx.try_recv() == Err(TryRecvError::Empty) {
+        assert!(Instant::now() < deadline, "the task wasn't dropped");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
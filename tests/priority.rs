@@ -0,0 +1,86 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{
+    task::{self, Priority},
+    AgnostikExecutor,
+};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+type Order = Arc<Mutex<Vec<&'static str>>>;
+
+async fn record(order: Order, name: &'static str) -> &'static str {
+    order.lock().unwrap().push(name);
+    name
+}
+
+/// Keeps the tests from changing the number of priority workers while another one runs.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[test]
+fn test_high_priority_starts_first() {
+    let _lock = lock();
+    // a single worker makes the other tasks queue up, while the first one runs
+    task::set_priority_workers(1);
+
+    let order = Order::default();
+    let names = agnostik::block_on({
+        let order = order.clone();
+        async move {
+            let first = agnostik::executor().spawn_with_priority(Priority::Low, {
+                let order = order.clone();
+                async move {
+                    let executor = agnostik::executor();
+                    let mut handles = Vec::new();
+                    for (priority, name) in &[
+                        (Priority::Low, "low 1"),
+                        (Priority::Normal, "normal"),
+                        (Priority::Low, "low 2"),
+                        (Priority::High, "high"),
+                    ] {
+                        let task = record(order.clone(), name);
+                        handles.push(executor.spawn_with_priority(*priority, task));
+                    }
+                    order.lock().unwrap().push("first");
+                    handles
+                }
+            });
+
+            let mut names = Vec::new();
+            for handle in first.await {
+                names.push(handle.await);
+            }
+            names
+        }
+    });
+    assert_eq!(names, ["low 1", "normal", "low 2", "high"]);
+    assert_eq!(
+        *order.lock().unwrap(),
+        ["first", "high", "normal", "low 1", "low 2"]
+    );
+}
+
+#[test]
+fn test_priority_task_panic() {
+    let _lock = lock();
+    task::set_priority_workers(1);
+
+    // the panic is returned to the handle, and the workers keep running new tasks
+    agnostik::block_on(async {
+        let handle = agnostik::executor().spawn_with_priority(Priority::Normal, async {
+            panic!("boom")
+        });
+        assert!(handle.try_join().await.unwrap_err().is_panic());
+        let handle = agnostik::executor().spawn_with_priority(Priority::High, async { 42 });
+        assert_eq!(handle.await, 42);
+    });
+}
@@ -0,0 +1,71 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{
+    task::{self, Priority},
+    AgnostikExecutor, Pool,
+};
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    thread,
+};
+
+/// Keeps the tests from changing the number of priority workers while another one runs.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Locks the other tests out, and lets a single worker run the prioritized tasks.
+fn single_worker() -> MutexGuard<'static, ()> {
+    let lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    task::set_priority_workers(1);
+    lock
+}
+
+fn pool() -> Pool {
+    Pool::builder()
+        .threads(1)
+        .name("prioritized")
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_priority_task_runs_on_pool() {
+    let _lock = single_worker();
+    let pool = pool();
+    let handle = pool.spawn_with_priority(Priority::High, async {
+        thread::current().name().map(String::from)
+    });
+    assert_eq!(pool.block_on(handle).as_deref(), Some("prioritized"));
+}
+
+#[test]
+fn test_drop_pool_with_pending_priority_task() {
+    let _lock = single_worker();
+    let pool = pool();
+    // the worker of the pool waits for the pending task, until the pool is dropped
+    let pending = pool.spawn_with_priority(Priority::Low, futures::future::pending::<()>());
+    drop(pool);
+    let err = agnostik::block_on(pending.try_join()).unwrap_err();
+    assert!(err.is_cancelled());
+}
+
+#[test]
+fn test_global_priority_workers_outlive_dropped_pool() {
+    let _lock = single_worker();
+    let pool = pool();
+    let pending = pool.spawn_with_priority(Priority::Low, futures::future::pending::<()>());
+    drop(pool);
+    drop(pending);
+
+    let answer = agnostik::block_on(async {
+        agnostik::executor()
+            .spawn_with_priority(Priority::Low, async { 42 })
+            .await
+    });
+    assert_eq!(answer, 42);
+}
@@ -0,0 +1,132 @@
+#![cfg(all(
+    feature = "prometheus",
+    any(
+        feature = "runtime_bastion",
+        feature = "runtime_asyncstd",
+        feature = "runtime_tokio",
+        feature = "runtime_tokio1",
+        feature = "runtime_smol"
+    )
+))]
+
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    thread,
+    time::Duration,
+};
+
+const EMPTY: &str = "\
+# HELP agnostik_tasks_spawned_total Number of tasks that were spawned.
+# TYPE agnostik_tasks_spawned_total counter
+agnostik_tasks_spawned_total 0
+# HELP agnostik_tasks_completed_total Number of tasks that ran to completion.
+# TYPE agnostik_tasks_completed_total counter
+agnostik_tasks_completed_total 0
+# HELP agnostik_tasks_panicked_total Number of tasks that panicked.
+# TYPE agnostik_tasks_panicked_total counter
+agnostik_tasks_panicked_total 0
+# HELP agnostik_tasks_cancelled_total Number of tasks that were cancelled.
+# TYPE agnostik_tasks_cancelled_total counter
+agnostik_tasks_cancelled_total 0
+# HELP agnostik_tasks_alive Number of tasks that didn't finish yet.
+# TYPE agnostik_tasks_alive gauge
+agnostik_tasks_alive 0
+# HELP agnostik_blocking_threads Number of threads that ran blocking tasks.
+# TYPE agnostik_blocking_threads gauge
+agnostik_blocking_threads 0
+# HELP agnostik_blocking_tasks_running Number of blocking tasks that are running.
+# TYPE agnostik_blocking_tasks_running gauge
+agnostik_blocking_tasks_running 0
+# HELP agnostik_blocking_queue_depth Number of blocking tasks that wait for a thread.
+# TYPE agnostik_blocking_queue_depth gauge
+agnostik_blocking_queue_depth 0
+# HELP agnostik_worker_polls_total Number of polls of tasks per worker thread.
+# TYPE agnostik_worker_polls_total counter
+# HELP agnostik_poll_duration_seconds Durations of the polls of tasks per task name.
+# TYPE agnostik_poll_duration_seconds histogram
+";
+
+const SLEEPY: &str = "\
+agnostik_poll_duration_seconds_bucket{task=\"sleepy \\\"task\\\"\",le=\"0.00001\"} 0
+agnostik_poll_duration_seconds_bucket{task=\"sleepy \\\"task\\\"\",le=\"0.0001\"} 0
+agnostik_poll_duration_seconds_bucket{task=\"sleepy \\\"task\\\"\",le=\"0.001\"} 0
+agnostik_poll_duration_seconds_bucket{task=\"sleepy \\\"task\\\"\",le=\"0.01\"} 0
+agnostik_poll_duration_seconds_bucket{task=\"sleepy \\\"task\\\"\",le=\"0.1\"} 1
+agnostik_poll_duration_seconds_bucket{task=\"sleepy \\\"task\\\"\",le=\"1\"} 1
+agnostik_poll_duration_seconds_bucket{task=\"sleepy \\\"task\\\"\",le=\"+Inf\"} 1
+";
+
+/// Keeps the tasks of the other tests from being counted, since the metrics are global.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the comments and the names of the samples without labels, which don't depend
+/// on the tasks that ran before.
+fn families(out: &str) -> Vec<&str> {
+    let lines = out.lines().filter(|line| !line.contains('{'));
+    lines
+        .map(|line| {
+            if line.starts_with('#') {
+                line
+            } else {
+                line.split(' ').next().unwrap()
+            }
+        })
+        .collect()
+}
+
+/// Returns the value of the sample without labels.
+fn value(out: &str, name: &str) -> u64 {
+    let prefix = format!("{} ", name);
+    let line = out.lines().find(|line| line.starts_with(&prefix)).unwrap();
+    line[prefix.len()..].parse().unwrap()
+}
+
+#[test]
+fn test_encode_metric_families() {
+    let _lock = lock();
+    let mut out = String::new();
+    agnostik::metrics::encode_prometheus(&mut out);
+    assert_eq!(families(&out), families(EMPTY));
+}
+
+#[test]
+fn test_encode_task_counters() {
+    let _lock = lock();
+    let mut before = String::new();
+    agnostik::metrics::encode_prometheus(&mut before);
+
+    agnostik::block_on(async { agnostik::spawn(async { 1 }).await });
+
+    let mut out = String::new();
+    agnostik::metrics().encode_prometheus(&mut out);
+    for name in &[
+        "agnostik_tasks_spawned_total",
+        "agnostik_tasks_completed_total",
+    ] {
+        assert_eq!(value(&out, name), value(&before, name) + 1);
+    }
+    assert!(out.contains("\nagnostik_worker_polls_total{worker=\"0\""));
+}
+
+#[test]
+fn test_encode_poll_histogram() {
+    let _lock = lock();
+    agnostik::PollTiming::new().enable();
+    agnostik::block_on(async {
+        agnostik::task::Builder::new()
+            .name("sleepy \"task\"")
+            .spawn(async { thread::sleep(Duration::from_millis(20)) })
+            .await;
+    });
+    agnostik::PollTiming::disable();
+
+    let mut out = String::new();
+    agnostik::metrics().encode_prometheus(&mut out);
+    assert!(out.contains(SLEEPY));
+    assert!(out.contains("\nagnostik_poll_duration_seconds_sum{task=\"sleepy \\\"task\\\"\"} 0.0"));
+    assert!(out.ends_with("agnostik_poll_duration_seconds_count{task=\"sleepy \\\"task\\\"\"} 1\n"));
+}
@@ -0,0 +1,157 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{time, RetryPolicy};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Keeps the tests from advancing the global clock at the same time.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Pauses the clock while the other tests are locked out, and resumes it when dropped.
+struct Paused {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Paused {
+    fn new() -> Self {
+        let lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        time::pause();
+        Self { _lock: lock }
+    }
+}
+
+impl Drop for Paused {
+    fn drop(&mut self) {
+        time::resume();
+    }
+}
+
+/// Runs the future on the executor, and advances the paused clock to the next timer
+/// whenever the future waits for one, until it finished.
+fn run_paused<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let done = Arc::new(AtomicBool::new(false));
+    let handle = agnostik::block_on({
+        let done = done.clone();
+        async move {
+            agnostik::spawn(async move {
+                let output = future.await;
+                done.store(true, Ordering::SeqCst);
+                output
+            })
+        }
+    });
+    while !done.load(Ordering::SeqCst) {
+        let now = time::now();
+        // a timer whose deadline passed is about to wake the future, which isn't idle yet
+        match time::next_deadline() {
+            Some(deadline) if deadline > now => time::advance(deadline - now),
+            _ => thread::yield_now(),
+        }
+    }
+    agnostik::block_on(handle)
+}
+
+/// Retries an operation that fails `failures` times, and returns the result and the
+/// delays between the attempts.
+fn attempts(policy: RetryPolicy, failures: usize) -> (Result<usize, usize>, Vec<Duration>) {
+    let _paused = Paused::new();
+    let times = Arc::new(Mutex::new(Vec::<Instant>::new()));
+    let result = run_paused({
+        let times = times.clone();
+        agnostik::retry(policy, move || {
+            let mut times = times.lock().unwrap();
+            times.push(time::now());
+            let attempt = times.len();
+            async move {
+                if attempt > failures {
+                    Ok(attempt)
+                } else {
+                    Err(attempt)
+                }
+            }
+        })
+    });
+    let times = times.lock().unwrap();
+    let delays = times.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    (result, delays)
+}
+
+fn assert_delays(delays: &[Duration], expected: &[(u64, u64)]) {
+    assert_eq!(delays.len(), expected.len(), "{:?}", delays);
+    for (delay, (min, max)) in delays.iter().zip(expected) {
+        let (min, max) = (Duration::from_secs(*min), Duration::from_secs(*max));
+        assert!(
+            *delay >= min && *delay <= max,
+            "{:?} isn't within {:?} and {:?}",
+            delays,
+            min,
+            max
+        );
+    }
+}
+
+#[test]
+fn test_exponential_backoff() {
+    let exponential = RetryPolicy::exponential(Duration::from_secs(1), Duration::from_secs(4));
+    let (result, delays) = attempts(exponential.max_attempts(10), 4);
+    assert_eq!(result, Ok(5));
+    assert_delays(&delays, &[(1, 1), (2, 2), (4, 4), (4, 4)]);
+}
+
+#[test]
+fn test_last_error_is_returned() {
+    let exponential = RetryPolicy::exponential(Duration::from_secs(1), Duration::from_secs(4));
+    let (result, delays) = attempts(exponential, 5);
+    assert_eq!(result, Err(3));
+    assert_delays(&delays, &[(1, 1), (2, 2)]);
+}
+
+#[test]
+fn test_constant_backoff() {
+    let constant = RetryPolicy::constant(Duration::from_secs(2)).max_attempts(10);
+    let (result, delays) = attempts(constant, 2);
+    assert_eq!(result, Ok(3));
+    assert_delays(&delays, &[(2, 2), (2, 2)]);
+}
+
+#[test]
+fn test_deadline() {
+    let constant = RetryPolicy::constant(Duration::from_secs(2)).max_attempts(10);
+    let (result, delays) = attempts(constant.deadline(Duration::from_secs(5)), 5);
+    assert_eq!(result, Err(3));
+    assert_delays(&delays, &[(2, 2), (2, 2)]);
+}
+
+#[test]
+fn test_jitter() {
+    let jittered = RetryPolicy::constant(Duration::from_secs(8))
+        .jitter()
+        .max_attempts(4);
+    let (result, delays) = attempts(jittered, 5);
+    assert_eq!(result, Err(4));
+    assert_delays(&delays, &[(4, 8), (4, 8), (4, 8)]);
+}
+
+#[test]
+fn test_first_attempt_succeeds() {
+    let (result, delays) = attempts(RetryPolicy::constant(Duration::from_secs(1)), 0);
+    assert_eq!(result, Ok(1));
+    assert!(delays.is_empty());
+}
@@ -0,0 +1,88 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{channel::oneshot, ShutdownReport};
+use once_cell::sync::Lazy;
+use std::{thread, time::Duration};
+
+/// The outcome of shutting down the global executor with three tasks, one of them blocking.
+struct Shutdown {
+    report: ShutdownReport,
+    quick: usize,
+    stuck_cancelled: bool,
+    blocking: usize,
+}
+
+/// Shuts down the global executor the first time it's used, since a shutdown can't be
+/// undone, and the tests only check different parts of its outcome.
+static SHUTDOWN: Lazy<Shutdown> = Lazy::new(|| {
+    let (tx, rx) = oneshot::channel::<usize>();
+    let (release_tx, release_rx) = oneshot::channel::<()>();
+    let (started_tx, started_rx) = oneshot::channel::<()>();
+    let (quick, stuck, blocking) = agnostik::block_on(async move {
+        let quick = agnostik::spawn(async move { rx.await.unwrap() });
+        let stuck = agnostik::spawn(futures::future::pending::<()>());
+        let blocking = agnostik::spawn_blocking(move || {
+            started_tx.send(()).unwrap();
+            release_rx.recv_blocking().unwrap();
+            7
+        });
+        (quick, stuck, blocking)
+    });
+    started_rx.recv_blocking().unwrap();
+
+    // the tasks finish while the shutdown waits for them
+    thread::spawn(move || {
+        while !agnostik::is_shut_down() {
+            thread::yield_now();
+        }
+        tx.send(42).unwrap();
+        release_tx.send(()).unwrap();
+    });
+
+    let report = agnostik::shutdown(Duration::from_millis(500));
+    futures::executor::block_on(async {
+        Shutdown {
+            report,
+            quick: quick.await,
+            stuck_cancelled: stuck.try_join().await.unwrap_err().is_cancelled(),
+            blocking: blocking.await,
+        }
+    })
+});
+
+#[test]
+fn test_shutdown_report() {
+    let report = SHUTDOWN.report;
+    assert_eq!(report.completed(), 1);
+    assert_eq!(report.cancelled(), 1);
+    assert_eq!(report.completed_blocking(), 1);
+    assert_eq!(report.cancelled_blocking(), 0);
+    assert!(!report.is_graceful());
+}
+
+#[test]
+fn test_shutdown_waits_for_tasks() {
+    assert_eq!(SHUTDOWN.quick, 42);
+    assert_eq!(SHUTDOWN.blocking, 7);
+}
+
+#[test]
+fn test_shutdown_cancels_stuck_tasks() {
+    assert!(SHUTDOWN.stuck_cancelled);
+}
+
+#[test]
+fn test_spawn_after_shutdown_is_rejected() {
+    Lazy::force(&SHUTDOWN);
+    let rejected = agnostik::spawn(async { 1 });
+    assert!(rejected.is_rejected());
+    let err = futures::executor::block_on(rejected.try_join()).unwrap_err();
+    assert!(err.is_cancelled());
+    assert!(agnostik::spawn_blocking(|| 1).is_rejected());
+}
@@ -0,0 +1,179 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::supervisor::{Backoff, Restart, Strategy, Supervisor};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Counts how often a child was started.
+fn counter() -> Arc<AtomicUsize> {
+    Arc::new(AtomicUsize::new(0))
+}
+
+#[test]
+fn test_one_for_one() {
+    let (flaky, once) = (counter(), counter());
+    let supervisor = Supervisor::new(Strategy::OneForOne)
+        .child_with("flaky", Restart::Transient, {
+            let flaky = flaky.clone();
+            move || {
+                let starts = flaky.fetch_add(1, Ordering::SeqCst) + 1;
+                async move { assert!(starts >= 3, "failed") }
+            }
+        })
+        .child_with("once", Restart::Temporary, {
+            let once = once.clone();
+            move || {
+                once.fetch_add(1, Ordering::SeqCst);
+                async {}
+            }
+        });
+
+    agnostik::block_on(supervisor.run()).unwrap();
+    assert_eq!(flaky.load(Ordering::SeqCst), 3);
+    assert_eq!(once.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_one_for_all() {
+    let (failing, waiting) = (counter(), counter());
+    let supervisor = Supervisor::new(Strategy::OneForAll)
+        .child_with("failing", Restart::Transient, {
+            let failing = failing.clone();
+            move || {
+                let starts = failing.fetch_add(1, Ordering::SeqCst) + 1;
+                async move { assert!(starts >= 2, "failed") }
+            }
+        })
+        .child_with("waiting", Restart::Transient, {
+            let waiting = waiting.clone();
+            move || {
+                let starts = waiting.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    if starts == 1 {
+                        futures::future::pending::<()>().await;
+                    }
+                }
+            }
+        });
+
+    agnostik::block_on(supervisor.run()).unwrap();
+    assert_eq!(failing.load(Ordering::SeqCst), 2);
+    assert_eq!(waiting.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_rest_for_one() {
+    let (first, failing, last) = (counter(), counter(), counter());
+    let supervisor = Supervisor::new(Strategy::RestForOne)
+        .child_with("first", Restart::Transient, {
+            let first = first.clone();
+            move || {
+                first.fetch_add(1, Ordering::SeqCst);
+                agnostik::time::sleep(Duration::from_millis(50))
+            }
+        })
+        .child_with("failing", Restart::Transient, {
+            let failing = failing.clone();
+            move || {
+                let starts = failing.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    agnostik::time::sleep(Duration::from_millis(10)).await;
+                    assert!(starts >= 2, "failed");
+                }
+            }
+        })
+        .child_with("last", Restart::Transient, {
+            let last = last.clone();
+            move || {
+                last.fetch_add(1, Ordering::SeqCst);
+                agnostik::time::sleep(Duration::from_millis(50))
+            }
+        });
+
+    agnostik::block_on(supervisor.run()).unwrap();
+    assert_eq!(first.load(Ordering::SeqCst), 1);
+    assert_eq!(failing.load(Ordering::SeqCst), 2);
+    assert_eq!(last.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_restart_intensity() {
+    let (crasher, steady) = (counter(), counter());
+    let supervisor = Supervisor::new(Strategy::OneForOne)
+        .restart_intensity(2, Duration::from_secs(10))
+        .child("crasher", {
+            let crasher = crasher.clone();
+            move || {
+                crasher.fetch_add(1, Ordering::SeqCst);
+                async { panic!("failed") }
+            }
+        })
+        .child("steady", {
+            let steady = steady.clone();
+            move || {
+                steady.fetch_add(1, Ordering::SeqCst);
+                futures::future::pending()
+            }
+        });
+
+    let err = agnostik::block_on(supervisor.run()).unwrap_err();
+    assert_eq!(err.child(), "crasher");
+    assert!(err.to_string().starts_with("child 'crasher' failed after 2 restarts"));
+    assert_eq!(crasher.load(Ordering::SeqCst), 3);
+    assert_eq!(steady.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_backoff() {
+    let flaky = counter();
+    let supervisor = Supervisor::new(Strategy::OneForOne)
+        .backoff(Backoff::fixed(Duration::from_millis(50)))
+        .child_with("flaky", Restart::Transient, {
+            let flaky = flaky.clone();
+            move || {
+                let starts = flaky.fetch_add(1, Ordering::SeqCst) + 1;
+                async move { assert!(starts >= 2, "failed") }
+            }
+        });
+
+    let start = Instant::now();
+    agnostik::block_on(supervisor.run()).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(flaky.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_sleep() {
+    agnostik::block_on(async {
+        let start = Instant::now();
+        let (tx, mut rx) = agnostik::channel::mpsc::unbounded_channel();
+        let mut handles = Vec::new();
+        for &millis in &[30, 10, 20] {
+            let tx = tx.clone();
+            let sleep = agnostik::time::sleep(Duration::from_millis(millis));
+            handles.push(agnostik::spawn(async move {
+                sleep.await;
+                tx.send(millis).unwrap();
+            }));
+        }
+        drop(tx);
+
+        let mut order = Vec::new();
+        while let Some(millis) = rx.recv().await {
+            order.push(millis);
+        }
+        assert_eq!(order, [10, 20, 30]);
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    });
+}
@@ -0,0 +1,237 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::sync::{Barrier, Mutex, Notify, OnceCell, RwLock, Semaphore};
+use futures::future::join_all;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+/// Yields to the executor once.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[test]
+fn test_mutex() {
+    let counter = agnostik::block_on(async {
+        let counter = Arc::new(Mutex::new(0));
+        let handles = (0..50)
+            .map(|_| {
+                let counter = counter.clone();
+                agnostik::spawn(async move {
+                    let mut guard = counter.lock().await;
+                    let value = *guard;
+                    YieldNow(false).await;
+                    *guard = value + 1;
+                })
+            })
+            .collect::<Vec<_>>();
+        join_all(handles).await;
+
+        let value = *counter.lock().await;
+        value
+    });
+    assert_eq!(counter, 50);
+}
+
+#[test]
+fn test_mutex_owned_guard() {
+    agnostik::block_on(async {
+        let mutex = Arc::new(Mutex::new(String::new()));
+        let guard = mutex.clone().lock_owned().await;
+        assert!(mutex.try_lock().is_err());
+
+        agnostik::spawn(async move {
+            let mut guard = guard;
+            guard.push_str("hello");
+        })
+        .await;
+
+        assert_eq!(*mutex.try_lock().unwrap(), "hello");
+    });
+}
+
+#[test]
+fn test_rwlock() {
+    agnostik::block_on(async {
+        let lock = RwLock::new(1);
+
+        let first = lock.read().await;
+        let second = lock.read().await;
+        assert_eq!(*first + *second, 2);
+        assert!(lock.try_write().is_err());
+        drop((first, second));
+
+        let mut guard = lock.write().await;
+        *guard += 1;
+        assert!(lock.try_read().is_err());
+        drop(guard);
+
+        assert_eq!(*lock.read().await, 2);
+    });
+}
+
+#[test]
+fn test_rwlock_writer_is_not_starved() {
+    agnostik::block_on(async {
+        let lock = Arc::new(RwLock::new(0));
+        let reader = lock.clone().read_owned().await;
+
+        let writer = agnostik::spawn({
+            let lock = lock.clone();
+            async move { *lock.write().await += 1 }
+        });
+        while lock.try_read().is_ok() {
+            YieldNow(false).await;
+        }
+
+        // a reader that arrives after the writer has to wait for it
+        let late_reader = agnostik::spawn({
+            let lock = lock.clone();
+            async move { *lock.read().await }
+        });
+
+        drop(reader);
+        writer.await;
+        assert_eq!(late_reader.await, 1);
+    });
+}
+
+#[test]
+fn test_semaphore_fairness() {
+    agnostik::block_on(async {
+        let semaphore = Semaphore::new(1);
+        let permit = semaphore.acquire().await;
+
+        let mut many = Box::pin(semaphore.acquire_many(2));
+        assert!(futures::poll!(many.as_mut()).is_pending());
+
+        // a single permit is available, but the queued request comes first
+        semaphore.add_permits(1);
+        assert!(semaphore.try_acquire().is_err());
+
+        drop(permit);
+        many.await.forget();
+        assert_eq!(semaphore.available_permits(), 0);
+    });
+}
+
+#[test]
+fn test_semaphore_owned_permit() {
+    agnostik::block_on(async {
+        let semaphore = Arc::new(Semaphore::new(2));
+        let handles = (0..10)
+            .map(|_| {
+                let semaphore = semaphore.clone();
+                async move {
+                    let permit = semaphore.acquire_owned().await;
+                    agnostik::spawn(async move {
+                        YieldNow(false).await;
+                        drop(permit);
+                    })
+                    .await
+                }
+            })
+            .collect::<Vec<_>>();
+
+        join_all(handles).await;
+        assert_eq!(semaphore.available_permits(), 2);
+    });
+}
+
+#[test]
+fn test_barrier() {
+    let leaders = agnostik::block_on(async {
+        let barrier = Arc::new(Barrier::new(10));
+        let handles = (0..10)
+            .map(|_| {
+                let barrier = barrier.clone();
+                agnostik::spawn(async move { barrier.wait().await.is_leader() })
+            })
+            .collect::<Vec<_>>();
+        join_all(handles).await
+    });
+    assert_eq!(leaders.into_iter().filter(|leader| *leader).count(), 1);
+}
+
+#[test]
+fn test_notify() {
+    agnostik::block_on(async {
+        let notify = Arc::new(Notify::new());
+
+        // the permit is stored if nobody is waiting
+        notify.notify_one();
+        notify.notified().await;
+
+        let woken = Arc::new(AtomicUsize::new(0));
+        let started = Arc::new(Barrier::new(4));
+        let handles = (0..3)
+            .map(|_| {
+                let (notify, woken, started) = (notify.clone(), woken.clone(), started.clone());
+                agnostik::spawn(async move {
+                    let mut notified = Box::pin(notify.notified());
+                    assert!(futures::poll!(notified.as_mut()).is_pending());
+                    started.wait().await;
+                    notified.await;
+                    woken.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        started.wait().await;
+        notify.notify_waiters();
+        join_all(handles).await;
+        assert_eq!(woken.load(Ordering::SeqCst), 3);
+    });
+}
+
+#[test]
+fn test_once_cell() {
+    agnostik::block_on(async {
+        let cell = Arc::new(OnceCell::new());
+        let inits = Arc::new(AtomicUsize::new(0));
+
+        let handles = (0..10)
+            .map(|i| {
+                let (cell, inits) = (cell.clone(), inits.clone());
+                agnostik::spawn(async move {
+                    *cell
+                        .get_or_init(|| async {
+                            inits.fetch_add(1, Ordering::SeqCst);
+                            YieldNow(false).await;
+                            i
+                        })
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let values = join_all(handles).await;
+        assert_eq!(inits.load(Ordering::SeqCst), 1);
+        assert!(values.iter().all(|value| Some(value) == cell.get()));
+        assert!(cell.set(100).is_err());
+    });
+}
@@ -0,0 +1,670 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::{
+    channel::oneshot,
+    join_handle::{InnerJoinHandle, JoinHandle},
+    task::{self, CancellationToken, ConcurrentError, TaskGroup},
+    AgnostikExecutor,
+};
+use futures::{stream, FutureExt, StreamExt};
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn test_scope_borrows_from_stack() {
+    let (output, total) = agnostik::block_on(async {
+        agnostik::spawn_blocking(|| {
+            let values = (1..=100).collect::<Vec<usize>>();
+            let total = AtomicUsize::new(0);
+            let (values_ref, total_ref) = (&values, &total);
+
+            let output = agnostik::scope(|s| async move {
+                let handles = values_ref
+                    .chunks(10)
+                    .map(|chunk| {
+                        s.spawn(async move {
+                            let sum = chunk.iter().sum::<usize>();
+                            total_ref.fetch_add(sum, Ordering::SeqCst);
+                            sum
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                futures::future::join_all(handles).await
+            });
+            (output, total.into_inner())
+        })
+        .await
+    });
+
+    assert_eq!(output.iter().sum::<usize>(), 5050);
+    assert_eq!(total, 5050);
+}
+
+#[test]
+fn test_scope_waits_for_children() {
+    let counter = agnostik::block_on(async {
+        agnostik::spawn_blocking(|| {
+            let counter = AtomicUsize::new(0);
+            let counter_ref = &counter;
+
+            agnostik::scope(|s| async move {
+                for _ in 0..10 {
+                    s.spawn(async move {
+                        agnostik::spawn_blocking(|| thread::sleep(Duration::from_millis(10))).await;
+                        counter_ref.fetch_add(1, Ordering::SeqCst);
+                    });
+                }
+            });
+            counter.into_inner()
+        })
+        .await
+    });
+
+    assert_eq!(counter, 10);
+}
+
+#[test]
+fn test_scope_async_waits_for_children() {
+    agnostik::block_on(async {
+        let counter = AtomicUsize::new(0);
+        let counter_ref = &counter;
+
+        unsafe {
+            task::scope_async(|s| async move {
+                for _ in 0..10 {
+                    s.spawn(async move {
+                        counter_ref.fetch_add(1, Ordering::SeqCst);
+                    });
+                }
+            })
+        }
+        .await;
+
+        assert_eq!(counter.load(Ordering::SeqCst), 10);
+    });
+}
+
+#[test]
+fn test_scope_propagates_panics() {
+    let message = agnostik::block_on(async {
+        agnostik::spawn_blocking(|| {
+            let result = panic::catch_unwind(|| {
+                agnostik::scope(|s| async move {
+                    s.spawn(async { panic!("child panicked") });
+                });
+            });
+            result.unwrap_err().downcast_ref::<&str>().copied()
+        })
+        .await
+    });
+
+    assert_eq!(message, Some("child panicked"));
+}
+
+#[test]
+fn test_scope_cancels_children_if_the_closure_panics() {
+    let dropped = agnostik::block_on(async {
+        agnostik::spawn_blocking(|| {
+            struct SetOnDrop<'a>(&'a AtomicBool);
+
+            impl Drop for SetOnDrop<'_> {
+                fn drop(&mut self) {
+                    self.0.store(true, Ordering::SeqCst);
+                }
+            }
+
+            let dropped = AtomicBool::new(false);
+            let guard = SetOnDrop(&dropped);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                agnostik::scope(|s| -> futures::future::Ready<()> {
+                    // the child may be cancelled before it's polled, so it owns the guard
+                    s.spawn(async move {
+                        let _guard = guard;
+                        futures::future::pending::<()>().await;
+                    });
+                    panic!("closure panicked")
+                })
+            }));
+            assert!(result.is_err());
+            dropped.load(Ordering::SeqCst)
+        })
+        .await
+    });
+
+    assert!(dropped);
+}
+
+#[test]
+fn test_scope_cancels_children_on_drop() {
+    agnostik::block_on(async {
+        let (tx, rx) = oneshot::channel::<()>();
+        let (dropped_tx, dropped_rx) = oneshot::channel::<()>();
+
+        let scope = unsafe {
+            task::scope_async(|s| async move {
+                s.spawn(async move {
+                    let _guard = dropped_tx;
+                    drop(rx.await);
+                });
+                futures::future::pending::<()>().await;
+            })
+        };
+
+        let mut scope = Box::pin(scope);
+        assert!(futures::poll!(scope.as_mut()).is_pending());
+        drop(scope);
+
+        assert!(dropped_rx.await.is_err());
+        assert!(tx.send(()).is_err());
+    });
+}
+
+#[test]
+fn test_task_group_join_next() {
+    agnostik::block_on(async {
+        let mut group = TaskGroup::new();
+        for i in 0..10 {
+            group.spawn(async move { i * 2 });
+        }
+        assert_eq!(group.len(), 10);
+
+        let mut results = Vec::new();
+        while let Some(result) = group.join_next().await {
+            results.push(result.unwrap());
+        }
+        results.sort_unstable();
+
+        assert_eq!(results, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+        assert!(group.is_empty());
+    });
+}
+
+#[test]
+fn test_task_group_completion_order() {
+    agnostik::block_on(async {
+        let (tx, rx) = oneshot::channel::<()>();
+
+        let mut group = TaskGroup::new();
+        group.spawn(async move {
+            drop(rx.await);
+            "slow"
+        });
+        group.spawn(async { "fast" });
+
+        assert_eq!(group.join_next().await.unwrap().unwrap(), "fast");
+        tx.send(()).unwrap();
+        assert_eq!(group.join_next().await.unwrap().unwrap(), "slow");
+        assert!(group.join_next().await.is_none());
+    });
+}
+
+#[test]
+fn test_task_group_abort_all() {
+    agnostik::block_on(async {
+        let mut group = TaskGroup::new();
+        for _ in 0..5 {
+            group.spawn(futures::future::pending::<()>());
+        }
+        group.abort_all();
+
+        let results = group.collect::<Vec<_>>().await;
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|result| result.as_ref().unwrap_err().is_cancelled()));
+    });
+}
+
+#[test]
+fn test_task_group_panic() {
+    agnostik::block_on(async {
+        let mut group = TaskGroup::<()>::new();
+        group.spawn(async { panic!("task panicked") });
+
+        let err = group.join_next().await.unwrap().unwrap_err();
+        assert!(err.is_panic());
+        assert_eq!(err.into_panic().downcast_ref::<&str>(), Some(&"task panicked"));
+    });
+}
+
+#[test]
+fn test_task_group_aborts_on_drop() {
+    agnostik::block_on(async {
+        let (dropped_tx, dropped_rx) = oneshot::channel::<()>();
+
+        let mut group = TaskGroup::new();
+        let handle = group.spawn(async move {
+            let _guard = dropped_tx;
+            futures::future::pending::<()>().await;
+        });
+        drop(group);
+
+        assert!(dropped_rx.await.is_err());
+        assert!(handle.is_finished());
+    });
+}
+
+#[test]
+fn test_for_each_concurrent_limit() {
+    agnostik::block_on(async {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let result = task::for_each_concurrent(stream::iter(0..50), 4, |_| {
+            let (in_flight, max_in_flight) = (in_flight.clone(), max_in_flight.clone());
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                agnostik::spawn_blocking(|| thread::sleep(Duration::from_millis(1))).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok::<_, ()>(())
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 4);
+    });
+}
+
+#[test]
+fn test_map_concurrent_ordered() {
+    agnostik::block_on(async {
+        let outputs = task::map_concurrent(task::iter(0..20u64), 5, |i| async move {
+            agnostik::spawn_blocking(move || thread::sleep(Duration::from_millis(20 - i))).await;
+            Ok::<_, ()>(i * i)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(outputs, (0..20).map(|i| i * i).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn test_map_concurrent_unordered() {
+    agnostik::block_on(async {
+        let mut outputs =
+            task::map_concurrent_unordered(stream::iter(0..20), 5, |i| async move { Ok::<_, ()>(i) })
+                .await
+                .unwrap();
+        outputs.sort_unstable();
+
+        assert_eq!(outputs, (0..20).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn test_map_concurrent_propagates_errors() {
+    agnostik::block_on(async {
+        let result = task::map_concurrent(stream::iter(0..100), 3, |i| async move {
+            if i == 7 {
+                Err("seven")
+            } else {
+                Ok(i)
+            }
+        })
+        .await;
+
+        match result {
+            Err(ConcurrentError::Failed { index, error }) => {
+                assert_eq!(index, 7);
+                assert_eq!(error, "seven");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let result = task::for_each_concurrent(stream::iter(0..10), 3, |i| async move {
+            if i == 3 {
+                panic!("item panicked");
+            }
+            Ok::<_, ()>(())
+        })
+        .await;
+
+        let err = result.unwrap_err();
+        assert_eq!(err.index(), 3);
+        assert!(matches!(err, ConcurrentError::Join { ref error, .. } if error.is_panic()));
+    });
+}
+
+/// An executor that drops the future of its third task, like an executor that is shut down.
+struct DropsThirdTask(AtomicUsize);
+
+impl AgnostikExecutor for DropsThirdTask {
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        if self.0.fetch_add(1, Ordering::SeqCst) == 2 {
+            drop(future);
+            return agnostik::spawn(futures::future::pending());
+        }
+        agnostik::spawn(future)
+    }
+
+    fn spawn_blocking<F, T>(&self, task: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        agnostik::spawn_blocking(task)
+    }
+
+    fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        agnostik::block_on(future)
+    }
+}
+
+#[test]
+fn test_map_concurrent_reports_dropped_tasks() {
+    agnostik::block_on(async {
+        let executor = DropsThirdTask(AtomicUsize::new(0));
+        let result = task::map_concurrent_on(&executor, stream::iter(0..5), 1, |i| async move {
+            Ok::<_, ()>(i)
+        })
+        .await;
+
+        let err = result.unwrap_err();
+        assert_eq!(err.index(), 2);
+        assert!(matches!(err, ConcurrentError::Join { ref error, .. } if error.is_cancelled()));
+    });
+}
+
+#[test]
+fn test_cancellation_token_tree() {
+    agnostik::block_on(async {
+        let parent = CancellationToken::new();
+        let child = parent.child_token();
+        let grandchild = child.child_token();
+        let sibling = parent.child_token();
+
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(grandchild.is_cancelled());
+        assert!(!parent.is_cancelled());
+        assert!(!sibling.is_cancelled());
+
+        let waiter = {
+            let sibling = sibling.clone();
+            agnostik::spawn(async move { sibling.cancelled().await })
+        };
+        parent.cancel();
+        waiter.await;
+        assert!(sibling.is_cancelled());
+        assert!(parent.child_token().is_cancelled());
+    });
+}
+
+#[test]
+fn test_cancellation_reaches_grandchildren_of_dropped_tokens() {
+    let root = CancellationToken::new();
+    let grandchild = root.child_token().child_token();
+    let deep = root.child_token().child_token().child_token();
+
+    // creating more children forgets the ones that were dropped, but keeps the ancestors
+    // of tokens that are still alive
+    drop(root.child_token());
+    root.cancel();
+    assert!(grandchild.is_cancelled());
+    assert!(deep.is_cancelled());
+}
+
+#[test]
+fn test_spawn_with_token() {
+    agnostik::block_on(async {
+        let token = CancellationToken::new();
+        let (dropped_tx, dropped_rx) = oneshot::channel::<()>();
+
+        let cancelled = agnostik::executor().spawn_with_token(&token.child_token(), async move {
+            let _guard = dropped_tx;
+            futures::future::pending::<()>().await
+        });
+        let completed = agnostik::executor().spawn_with_token(&token, async { 42 });
+
+        assert_eq!(completed.await, Some(42));
+        token.cancel();
+        assert_eq!(cancelled.await, None);
+        assert!(dropped_rx.await.is_err());
+    });
+}
+
+#[test]
+fn test_spawn_with_lifecycle_hooks() {
+    let calls = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)]);
+    let hooks = {
+        let (started, completed, panicked) = (calls.clone(), calls.clone(), calls.clone());
+        task::LifecycleHooks::new()
+            .before_start(move || {
+                started[0].fetch_add(1, Ordering::SeqCst);
+            })
+            .after_complete(move || {
+                completed[1].fetch_add(1, Ordering::SeqCst);
+            })
+            .after_panic(move || {
+                panicked[2].fetch_add(1, Ordering::SeqCst);
+            })
+    };
+    let counts = || {
+        let count = |index: usize| calls[index].load(Ordering::SeqCst);
+        (count(0), count(1), count(2))
+    };
+
+    let first = hooks.clone();
+    agnostik::block_on(async move {
+        let handle = agnostik::executor().spawn_with_hooks(first, async {
+            agnostik::time::sleep(Duration::from_millis(1)).await;
+            42
+        });
+        assert_eq!(handle.await, 42);
+    });
+    assert_eq!(counts(), (1, 1, 0));
+
+    agnostik::block_on(async move {
+        // async-std and smol propagate the panic instead of returning a `JoinError`
+        let handle = agnostik::executor().spawn_with_hooks(hooks, async { panic!("boom") });
+        assert!(AssertUnwindSafe(handle.try_join())
+            .catch_unwind()
+            .await
+            .map_or(true, |res| res.unwrap_err().is_panic()));
+    });
+    assert_eq!(counts(), (2, 1, 1));
+}
+
+#[test]
+fn test_cancellation_drop_guard() {
+    let token = CancellationToken::new();
+    drop(token.clone().drop_guard());
+    assert!(token.is_cancelled());
+
+    let token = CancellationToken::new();
+    let token = token.drop_guard().disarm();
+    assert!(!token.is_cancelled());
+}
+
+#[test]
+fn test_builder_names_tasks() {
+    assert!(task::current().is_none());
+
+    agnostik::block_on(async {
+        let named = task::Builder::new().name("ingest-worker").spawn(async {
+            task::current().unwrap().name().map(String::from)
+        });
+        assert_eq!(named.await.as_deref(), Some("ingest-worker"));
+
+        let unnamed = agnostik::spawn(async { task::current().unwrap().name().is_none() });
+        assert!(unnamed.await);
+
+        let blocking = task::Builder::new()
+            .name("blocking-worker")
+            .spawn_blocking(|| task::current().unwrap().name().map(String::from));
+        assert_eq!(blocking.await.as_deref(), Some("blocking-worker"));
+    });
+}
+
+#[test]
+fn test_builder_name_in_join_error() {
+    let handle = agnostik::block_on(async {
+        task::Builder::new()
+            .name("ingest-worker")
+            .spawn(async { panic!("boom") })
+    });
+    let err = futures::executor::block_on(handle.try_join()).unwrap_err();
+    assert_eq!(err.task_name(), Some("ingest-worker"));
+    assert_eq!(err.to_string(), "task 'ingest-worker' panicked");
+
+    // the original payload is resumed, whether the task has a name or not
+    let handle = agnostik::block_on(async {
+        task::Builder::new()
+            .name("ingest-worker")
+            .spawn(async { panic!("boom") })
+    });
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| futures::executor::block_on(handle)))
+        .unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+}
+
+#[test]
+fn test_task_ids() {
+    assert!(task::try_id().is_none());
+
+    agnostik::block_on(async {
+        let global = agnostik::spawn(async { task::id() });
+        let direct = agnostik::executor().spawn(async { task::id() });
+        let blocking = agnostik::spawn_blocking(task::id);
+        let ids = [global.id(), direct.id(), blocking.id()];
+
+        assert_eq!(global.await, ids[0]);
+        assert_eq!(direct.await, ids[1]);
+        assert_eq!(blocking.await, ids[2]);
+        assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
+    });
+
+    // custom executors construct handles from an inner handle, which has no id
+    let custom = JoinHandle(InnerJoinHandle::Global(Box::pin(async { Ok(5) })));
+    assert!(custom.try_id().is_none());
+    assert_eq!(futures::executor::block_on(custom), 5);
+}
+
+agnostik::task_local! {
+    static REQUEST_ID: u64;
+    static USER: String;
+}
+
+#[test]
+fn test_task_local_scope() {
+    assert!(REQUEST_ID.try_with(|id| *id).is_err());
+
+    agnostik::block_on(async {
+        let first = agnostik::spawn(REQUEST_ID.scope(1, async {
+            let (tx, rx) = oneshot::channel::<()>();
+            let other = agnostik::spawn(REQUEST_ID.scope(2, async move {
+                tx.send(()).unwrap();
+                REQUEST_ID.get()
+            }));
+            rx.await.unwrap();
+            (REQUEST_ID.get(), other.await)
+        }));
+        assert_eq!(first.await, (1, 2));
+
+        let nested = REQUEST_ID.scope(3, async {
+            let inner = REQUEST_ID.scope(4, async { REQUEST_ID.get() }).await;
+            (inner, REQUEST_ID.get())
+        });
+        assert_eq!(nested.await, (4, 3));
+    });
+
+    assert_eq!(REQUEST_ID.sync_scope(5, || REQUEST_ID.get()), 5);
+    assert!(REQUEST_ID.try_with(|id| *id).is_err());
+}
+
+#[test]
+fn test_task_local_inheritance() {
+    agnostik::block_on(REQUEST_ID.scope(7, async {
+        USER.scope("ferris".to_string(), async {
+            let inherited = task::Builder::new()
+                .inherit(&REQUEST_ID)
+                .inherit(&USER)
+                .spawn(async { (REQUEST_ID.get(), USER.get()) });
+            assert_eq!(inherited.await, (7, "ferris".to_string()));
+
+            let blocking = task::Builder::new()
+                .inherit(&REQUEST_ID)
+                .spawn_blocking(|| (REQUEST_ID.get(), USER.try_with(|_| ()).is_err()));
+            assert_eq!(blocking.await, (7, true));
+
+            let plain = agnostik::spawn(async { REQUEST_ID.try_with(|id| *id).is_err() });
+            assert!(plain.await);
+        })
+        .await
+    }));
+}
+
+agnostik::task_local! {
+    static TRACE_ID: u64;
+}
+
+/// Counts how often the captured context is entered.
+struct CountingHook(Arc<AtomicUsize>);
+
+struct CountingContext(Arc<AtomicUsize>);
+
+impl task::SpawnHook for CountingHook {
+    fn capture(&self) -> Option<Box<dyn task::SpawnContext>> {
+        // only propagate the context of traced tasks
+        TRACE_ID.try_with(|_| ()).ok()?;
+        Some(Box::new(CountingContext(self.0.clone())))
+    }
+}
+
+impl task::SpawnContext for CountingContext {
+    fn enter(&mut self, f: &mut dyn FnMut()) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        f();
+    }
+}
+
+#[test]
+fn test_spawn_hooks() {
+    let entered = Arc::new(AtomicUsize::new(0));
+    task::add_spawn_hook(&TRACE_ID);
+    task::add_spawn_hook(CountingHook(entered.clone()));
+
+    agnostik::block_on(TRACE_ID.scope(9, async {
+        let spawned = agnostik::spawn(async { TRACE_ID.get() });
+        let direct = agnostik::executor().spawn(async { TRACE_ID.get() });
+        let blocking = agnostik::spawn_blocking(|| TRACE_ID.get());
+        let nested = agnostik::spawn(async {
+            agnostik::spawn(async { TRACE_ID.get() }).await
+        });
+        assert_eq!(spawned.await, 9);
+        assert_eq!(direct.await, 9);
+        assert_eq!(blocking.await, 9);
+        assert_eq!(nested.await, 9);
+    }));
+    assert!(entered.load(Ordering::SeqCst) >= 5);
+
+    let before = entered.load(Ordering::SeqCst);
+    agnostik::block_on(async {
+        let untraced = agnostik::spawn(async { TRACE_ID.try_with(|_| ()).is_err() });
+        assert!(untraced.await);
+    });
+    assert_eq!(entered.load(Ordering::SeqCst), before);
+}
@@ -0,0 +1,78 @@
+#![cfg(any(
+    feature = "runtime_bastion",
+    feature = "runtime_asyncstd",
+    feature = "runtime_tokio",
+    feature = "runtime_tokio1",
+    feature = "runtime_smol"
+))]
+
+use agnostik::time;
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+/// Keeps the tests from pausing and advancing the global clock at the same time.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[test]
+fn test_advance_wakes_timers() {
+    let _lock = lock();
+    time::pause();
+    let start = time::now();
+    // the timer is created before the clock is advanced, so its deadline is fixed
+    let sleep = time::sleep(Duration::from_secs(30 * 60));
+    let handle = agnostik::block_on(async {
+        agnostik::spawn(async {
+            sleep.await;
+            time::now()
+        })
+    });
+    time::advance(Duration::from_secs(60 * 60));
+    let woken = agnostik::block_on(handle);
+    assert!(woken >= start + Duration::from_secs(30 * 60));
+    time::resume();
+}
+
+#[test]
+fn test_resume_after_advance() {
+    let _lock = lock();
+    time::pause();
+    let start = time::now();
+    time::advance(Duration::from_secs(60 * 60));
+    time::resume();
+
+    // the clock keeps the time it was advanced by, instead of going back to the real time
+    let resumed = time::now();
+    assert!(resumed >= start + Duration::from_secs(60 * 60));
+    agnostik::block_on(async move {
+        time::sleep_until(start + Duration::from_secs(45 * 60)).await;
+        time::sleep(Duration::from_millis(10)).await;
+    });
+    assert!(time::now() >= resumed + Duration::from_millis(10));
+}
+
+#[test]
+fn test_advance_to_next_deadline() {
+    let _lock = lock();
+    time::pause();
+    let deadline = time::now() + Duration::from_secs(10 * 60);
+    let handle = agnostik::block_on(async move {
+        agnostik::spawn(async move { time::sleep_until(deadline).await })
+    });
+    // the timer is registered once the task was polled
+    while time::next_deadline().is_none() {
+        std::thread::yield_now();
+    }
+    assert_eq!(time::next_deadline(), Some(deadline));
+
+    time::advance(deadline - time::now());
+    agnostik::block_on(handle);
+    assert_eq!(time::now(), deadline);
+    assert_eq!(time::next_deadline(), None);
+    time::resume();
+}
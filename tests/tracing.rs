@@ -0,0 +1,222 @@
+#![cfg(all(
+    feature = "tracing",
+    any(
+        feature = "runtime_bastion",
+        feature = "runtime_asyncstd",
+        feature = "runtime_tokio",
+        feature = "runtime_tokio1",
+        feature = "runtime_smol"
+    )
+))]
+
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+/// Records the fields of all task spans, and the events emitted inside of them.
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, HashMap<String, String>>>,
+    events: Mutex<Vec<(Option<u64>, String)>>,
+}
+
+#[derive(Default)]
+struct Fields(HashMap<String, String>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl Subscriber for &'static Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        self.spans.lock().unwrap().insert(id, fields.0);
+        span::Id::from_u64(id)
+    }
+
+    fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        let mut spans = self.spans.lock().unwrap();
+        spans.get_mut(&span.into_u64()).unwrap().extend(fields.0);
+    }
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let parent = event.parent().map(span::Id::into_u64).or_else(|| {
+            CURRENT.with(|current| current.lock().unwrap().last().copied())
+        });
+        let message = fields.0.remove("message").unwrap_or_default();
+        self.events.lock().unwrap().push((parent, message));
+    }
+
+    fn enter(&self, span: &span::Id) {
+        CURRENT.with(|current| current.lock().unwrap().push(span.into_u64()));
+    }
+
+    fn exit(&self, _: &span::Id) {
+        CURRENT.with(|current| current.lock().unwrap().pop());
+    }
+}
+
+thread_local! {
+    static CURRENT: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+}
+
+impl Recorder {
+    /// Returns the fields of the span of the task, and the messages of its events, once
+    /// it finished, since a task may report its result before it's done.
+    fn task(&self, id: agnostik::task::Id) -> (HashMap<String, String>, Vec<String>) {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            let (fields, messages) = self.snapshot(id);
+            let finished = messages.iter().any(|m| FINISHED.contains(&m.as_str()));
+            if finished || Instant::now() >= deadline {
+                return (fields, messages);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn snapshot(&self, id: agnostik::task::Id) -> (HashMap<String, String>, Vec<String>) {
+        let spans = self.spans.lock().unwrap();
+        let (span, fields) = spans
+            .iter()
+            .find(|(_, fields)| fields.get("task.id") == Some(&id.to_string()))
+            .expect("task has no span");
+        let events = self.events.lock().unwrap();
+        let messages = events
+            .iter()
+            .filter(|(parent, _)| *parent == Some(*span))
+            .map(|(_, message)| message.clone())
+            .collect();
+        (fields.clone(), messages)
+    }
+
+    /// Waits until any task span contains an event with the message.
+    fn wait_for(&self, message: &str) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            let found = {
+                let spans = self.spans.lock().unwrap();
+                let events = self.events.lock().unwrap();
+                spans.keys().any(|span| {
+                    let mut events = events.iter();
+                    events.any(|(parent, m)| *parent == Some(*span) && m == message)
+                })
+            };
+            if found || Instant::now() >= deadline {
+                return found;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// The messages of the events, which are emitted when a task finished.
+const FINISHED: &[&str] = &["task completed", "task cancelled", "task panicked"];
+
+/// Returns the recorder, which is set as the global subscriber the first time, since
+/// it can't be replaced afterwards.
+fn recorder() -> &'static Recorder {
+    static RECORDER: Lazy<&'static Recorder> = Lazy::new(|| {
+        let recorder: &'static Recorder = Box::leak(Box::default());
+        tracing::subscriber::set_global_default(recorder).unwrap();
+        recorder
+    });
+    *RECORDER
+}
+
+#[test]
+fn test_completed_task_span() {
+    let recorder = recorder();
+    let completed = agnostik::block_on(async {
+        let completed = agnostik::task::Builder::new()
+            .name("worker")
+            .spawn(async { 1 });
+        let id = completed.id();
+        assert_eq!(completed.await, 1);
+        id
+    });
+
+    let (fields, messages) = recorder.task(completed);
+    assert_eq!(fields.get("task.name").map(String::as_str), Some("worker"));
+    assert_eq!(fields.get("task.kind").map(String::as_str), Some("async"));
+    assert!(fields["spawn.location"].contains("tests/tracing.rs"));
+    assert!(fields.contains_key("backend"));
+    assert_eq!(messages.first().map(String::as_str), Some("task spawned"));
+    assert!(messages.iter().any(|m| m == "task poll started"));
+    assert_eq!(messages.last().map(String::as_str), Some("task completed"));
+}
+
+#[test]
+fn test_cancelled_task_event() {
+    let recorder = recorder();
+    agnostik::block_on(async {
+        let mut group = agnostik::task::TaskGroup::new();
+        group.spawn(futures::future::pending::<()>());
+        group.abort_all();
+        assert!(group.join_next().await.unwrap().unwrap_err().is_cancelled());
+    });
+
+    assert!(recorder.wait_for("task cancelled"));
+}
+
+#[test]
+fn test_panicked_task_event() {
+    let recorder = recorder();
+    let panicked = agnostik::block_on(async {
+        let panicked = agnostik::spawn(async { panic!("boom") });
+        let id = panicked.id();
+        let result = futures::FutureExt::catch_unwind(AssertUnwindSafe(panicked)).await;
+        assert!(result.is_err());
+        id
+    });
+
+    let (_, messages) = recorder.task(panicked);
+    assert!(messages.iter().any(|m| m == "task panicked"));
+}
+
+#[test]
+fn test_blocking_task_span() {
+    let recorder = recorder();
+    let blocking = agnostik::block_on(async {
+        let blocking = agnostik::spawn_blocking(|| 2);
+        let id = blocking.id();
+        assert_eq!(blocking.await, 2);
+        id
+    });
+
+    let (fields, messages) = recorder.task(blocking);
+    assert_eq!(fields.get("task.kind").map(String::as_str), Some("blocking"));
+    assert_eq!(messages.last().map(String::as_str), Some("task completed"));
+}